@@ -0,0 +1,82 @@
+//! Conformance subcommand - checks .wvf fixtures against expected diagnostics
+
+use anyhow::{Result, bail};
+use std::path::PathBuf;
+use worldview_validator::conformance::{check, expectations, parse_expectations, render_expectations};
+use worldview_validator::validate;
+use worldview_validator::workspace::find_documents;
+
+pub fn run(dir: PathBuf, bless: bool) -> Result<()> {
+    let fixtures = find_documents(&dir)?;
+    if fixtures.is_empty() {
+        bail!("No .wvf fixtures found in {}", dir.display());
+    }
+
+    let mut passed = 0;
+    let mut failed = 0;
+
+    for path in &fixtures {
+        let expected_path = path.with_extension("expected");
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", path.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        if bless {
+            let rendered = render_expectations(&expectations(&validate(&content)));
+            if std::fs::read_to_string(&expected_path).ok().as_deref() != Some(rendered.as_str()) {
+                std::fs::write(&expected_path, rendered)?;
+                println!("Blessed {}", expected_path.display());
+            }
+            continue;
+        }
+
+        let expected = match std::fs::read_to_string(&expected_path) {
+            Ok(expected) => expected,
+            Err(e) => {
+                println!("FAIL {}", path.display());
+                println!("  cannot read {}: {} (run with --bless to create it)", expected_path.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+        let expected = match parse_expectations(&expected) {
+            Ok(expected) => expected,
+            Err(e) => {
+                println!("FAIL {}", path.display());
+                println!("  {}: {}", expected_path.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        let mismatch = check(&content, &expected);
+        if mismatch.is_empty() {
+            passed += 1;
+            continue;
+        }
+        println!("FAIL {}", path.display());
+        for e in &mismatch.missing {
+            println!("  missing     {}", e);
+        }
+        for e in &mismatch.unexpected {
+            println!("  unexpected  {}", e);
+        }
+        failed += 1;
+    }
+
+    if bless {
+        return Ok(());
+    }
+
+    println!();
+    println!("{} passed, {} failed", passed, failed);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
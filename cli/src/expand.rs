@@ -0,0 +1,65 @@
+//! Expand subcommand - renders every claim of .wvf files as an English sentence
+
+use crate::manifest::{Manifest, files_or_members};
+use crate::validate::{validate_documents, validator};
+use anyhow::Result;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use worldview_validator::expand::expand_document;
+use worldview_validator::workspace::WorkspaceFile;
+
+pub fn run(files: Vec<PathBuf>, stdin: bool, manifest: Option<&Manifest>) -> Result<()> {
+    let validator = validator(manifest.map(|m| m.lints.clone()).unwrap_or_default(), manifest)?;
+    let mut inputs = Vec::new();
+    let mut ok = true;
+
+    // Without explicit files, the manifest's members are checked as one workspace
+    let workspace = !stdin && files.is_empty();
+    if stdin {
+        let mut source = String::new();
+        io::stdin().read_to_string(&mut source)?;
+        inputs.push(WorkspaceFile { path: "<stdin>".into(), source });
+    } else {
+        for path in &files_or_members(files, manifest)? {
+            match WorkspaceFile::read(path) {
+                Ok(input) => inputs.push(input),
+                Err(e) => {
+                    eprintln!("Error reading {}: {}", path.display(), e);
+                    ok = false;
+                }
+            }
+        }
+    }
+
+    let several = inputs.len() > 1;
+    let results = validate_documents(&validator, &inputs, workspace);
+    for (i, (input, result)) in inputs.iter().zip(results).enumerate() {
+        let path = input.path.display();
+        // Lines the parser cannot place in the tree would be missing from the expansion
+        if !result.is_valid() {
+            let count = result.errors.len();
+            eprintln!(
+                "Skipping {}: {} error{} (run `worldview validate {}` for details)",
+                path,
+                count,
+                if count == 1 { "" } else { "s" },
+                path
+            );
+            ok = false;
+            continue;
+        }
+        if several {
+            if i > 0 {
+                println!();
+            }
+            println!("{}:", path);
+        }
+        print!("{}", expand_document(&result.document));
+    }
+
+    if ok {
+        Ok(())
+    } else {
+        std::process::exit(1);
+    }
+}
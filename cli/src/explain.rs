@@ -0,0 +1,28 @@
+//! Explain subcommand - prints the long description of a diagnostic code
+
+use anyhow::Result;
+use worldview_validator::codes;
+
+pub fn run(code: Option<String>) -> Result<()> {
+    let Some(code) = code else {
+        // No code given: list every code with its summary
+        for entry in codes::CODES {
+            println!("{}  {:<7}  {}", entry.code, entry.severity, entry.summary());
+        }
+        return Ok(());
+    };
+
+    match codes::lookup(&code) {
+        Some(entry) => {
+            println!("{} ({}): {}", entry.code, entry.severity, entry.name);
+            println!();
+            print!("{}", entry.explanation);
+            Ok(())
+        }
+        None => {
+            eprintln!("Error: unknown diagnostic code '{}'", code);
+            eprintln!("Run `worldview explain` to list all codes.");
+            std::process::exit(1);
+        }
+    }
+}
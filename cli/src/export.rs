@@ -0,0 +1,81 @@
+//! Export subcommand - renders a .wvf file for readers who do not know the notation
+
+use crate::manifest::Manifest;
+use crate::validate::validator;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use worldview_validator::markdown::to_markdown;
+use worldview_validator::workspace::WorkspaceFile;
+use worldview_validator::{ValidationResult, Validator};
+
+/// Output format for `worldview export`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// Concepts and facets as headings, claims as bullets
+    Markdown,
+}
+
+pub fn run(file: Option<PathBuf>, to: ExportFormat, output: Option<PathBuf>, manifest: Option<&Manifest>) -> Result<()> {
+    let validator = validator(manifest.map(|m| m.lints.clone()).unwrap_or_default(), manifest)?;
+    let (name, source) = match &file {
+        Some(path) => (
+            path.display().to_string(),
+            std::fs::read_to_string(path).with_context(|| format!("Error reading {}", path.display()))?,
+        ),
+        None => {
+            let mut source = String::new();
+            io::stdin().read_to_string(&mut source)?;
+            ("<stdin>".to_string(), source)
+        }
+    };
+
+    // Lines the parser cannot place in the tree would be missing from the export
+    let result = match (&file, manifest) {
+        (Some(path), Some(manifest)) => validate_member(&validator, path, &source, manifest)?,
+        _ => validator.validate(&source),
+    };
+    if !result.is_valid() {
+        eprintln!(
+            "Cannot export {}: {} error{} (run `worldview validate {}` for details)",
+            name,
+            result.errors.len(),
+            if result.errors.len() == 1 { "" } else { "s" },
+            name
+        );
+        std::process::exit(1);
+    }
+
+    let exported = match to {
+        ExportFormat::Markdown => to_markdown(&result.document),
+    };
+    match output {
+        Some(path) => {
+            std::fs::write(&path, exported)?;
+            println!("Exported {} to {}", name, path.display());
+        }
+        None => print!("{}", exported),
+    }
+    Ok(())
+}
+
+/// Validate a file with the rest of the workspace if it is a member, so its
+/// references may point into other files, or else on its own
+fn validate_member(validator: &Validator, path: &Path, source: &str, manifest: &Manifest) -> Result<ValidationResult> {
+    let canonical = path.canonicalize()?;
+    let members = manifest.members()?;
+    let Some(index) = members.iter().position(|m| m.canonicalize().is_ok_and(|m| m == canonical)) else {
+        return Ok(validator.validate(source));
+    };
+
+    let mut documents = Vec::new();
+    for (i, member) in members.iter().enumerate() {
+        documents.push(if i == index {
+            WorkspaceFile { path: member.clone(), source: source.to_string() }
+        } else {
+            WorkspaceFile::read(member).with_context(|| format!("Error reading {}", member.display()))?
+        });
+    }
+    Ok(validator.validate_workspace(&documents).swap_remove(index))
+}
@@ -0,0 +1,80 @@
+//! Fix subcommand - applies automatic fixes to .wvf files
+
+use anyhow::Result;
+use similar::TextDiff;
+use crate::manifest::{Manifest, files_or_members};
+use crate::validate::{validate_documents, validator};
+use std::path::PathBuf;
+use worldview_validator::render::Renderer;
+use worldview_validator::workspace::WorkspaceFile;
+
+pub fn run(files: Vec<PathBuf>, diff: bool, include_unsafe: bool, manifest: Option<&Manifest>) -> Result<()> {
+    let validator = validator(manifest.map(|m| m.lints.clone()).unwrap_or_default(), manifest)?;
+    let mut all_valid = true;
+
+    // Without explicit files, the manifest's members are fixed as one workspace,
+    // so references are only corrected against concepts it declares
+    let workspace = files.is_empty();
+    let mut documents = Vec::new();
+    for path in &files_or_members(files, manifest)? {
+        match WorkspaceFile::read(path) {
+            Ok(document) => documents.push(document),
+            Err(e) => {
+                eprintln!("Error reading {}: {}", path.display(), e);
+                all_valid = false;
+            }
+        }
+    }
+
+    let outcomes = if workspace {
+        validator.fix_workspace(&documents, include_unsafe)
+    } else {
+        documents.iter().map(|d| validator.fix_source(&d.source, include_unsafe)).collect()
+    };
+    let fixed: Vec<WorkspaceFile> = documents
+        .iter()
+        .zip(&outcomes)
+        .map(|(document, outcome)| WorkspaceFile {
+            path: document.path.clone(),
+            source: outcome.source.clone(),
+        })
+        .collect();
+    let results = validate_documents(&validator, &fixed, workspace);
+
+    for ((document, outcome), result) in documents.iter().zip(&outcomes).zip(results) {
+        let display = document.path.display().to_string();
+
+        if diff {
+            if !outcome.applied.is_empty() {
+                print!(
+                    "{}",
+                    TextDiff::from_lines(&document.source, &outcome.source)
+                        .unified_diff()
+                        .header(&display, &display)
+                );
+            }
+        } else if !outcome.applied.is_empty() {
+            std::fs::write(&document.path, &outcome.source)?;
+            println!(
+                "Fixed {}: {} fix{}",
+                display,
+                outcome.applied.len(),
+                if outcome.applied.len() == 1 { "" } else { "es" }
+            );
+        }
+
+        // Report whatever is left for a person to resolve
+        if !result.is_valid() {
+            all_valid = false;
+            if !diff {
+                eprint!("{}", Renderer::new().render(&result, &outcome.source, &display));
+            }
+        }
+    }
+
+    if all_valid {
+        Ok(())
+    } else {
+        std::process::exit(1);
+    }
+}
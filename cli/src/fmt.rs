@@ -0,0 +1,84 @@
+//! Fmt subcommand - rewrites .wvf files in canonical form
+
+use anyhow::Result;
+use std::io::{self, Read};
+use crate::manifest::{Manifest, files_or_members};
+use std::path::PathBuf;
+use worldview_validator::ValidationError;
+use worldview_validator::format::format_source;
+
+pub fn run(files: Vec<PathBuf>, stdin: bool, check: bool, manifest: Option<&Manifest>) -> Result<()> {
+    let mut ok = true;
+
+    if stdin {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        match format_source(&content) {
+            Ok(formatted) if check => {
+                if formatted != content {
+                    println!("<stdin> is not formatted");
+                    ok = false;
+                }
+            }
+            Ok(formatted) => print!("{}", formatted),
+            Err(errors) => {
+                report_errors("<stdin>", &errors);
+                ok = false;
+            }
+        }
+    } else {
+        for path in &files_or_members(files, manifest)? {
+            let content = match std::fs::read_to_string(path) {
+                Ok(content) => content,
+                Err(e) => {
+                    eprintln!("Error reading {}: {}", path.display(), e);
+                    ok = false;
+                    continue;
+                }
+            };
+
+            let formatted = match format_source(&content) {
+                Ok(formatted) => formatted,
+                Err(errors) => {
+                    report_errors(&path.display().to_string(), &errors);
+                    ok = false;
+                    continue;
+                }
+            };
+
+            if formatted == content {
+                continue;
+            }
+            if check {
+                println!("{} is not formatted", path.display());
+                ok = false;
+            } else {
+                std::fs::write(path, formatted)?;
+                println!("Formatted {}", path.display());
+            }
+        }
+    }
+
+    if ok {
+        Ok(())
+    } else {
+        std::process::exit(1);
+    }
+}
+
+/// Files with errors are left untouched: fix them first with `worldview validate`
+fn report_errors(path: &str, errors: &[ValidationError]) {
+    // Validation never reports this one, so it is shown here
+    if let [error @ ValidationError::UnstableFormat { .. }] = errors {
+        eprintln!("Skipping {}: {} [{}]", path, error, error.code());
+        return;
+    }
+    let count = errors.len();
+    eprintln!(
+        "Skipping {}: {} error{} (run `worldview validate {}` for details)",
+        path,
+        count,
+        if count == 1 { "" } else { "s" },
+        path
+    );
+}
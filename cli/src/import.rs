@@ -0,0 +1,84 @@
+//! Import subcommand - writes a .wvf file from a JSON or YAML document tree,
+//! or a Markdown outline
+//!
+//! JSON and YAML input is the `document` shape of `worldview parse` output (see
+//! `spec/parse.schema.json`); spans and empty lists may be left out. Markdown
+//! input is read as described in `worldview_validator::markdown`. Nothing is
+//! written unless the document reads back as the same tree (see
+//! `worldview_validator::format::import_tree`).
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::io::{self, IsTerminal, Read};
+use std::path::PathBuf;
+use worldview_validator::format::{TreeError, import_tree};
+use worldview_validator::markdown::from_markdown;
+use worldview_validator::render::Renderer;
+use worldview_validator::{Document, validate};
+
+/// Input format for `worldview import`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    /// A document tree, as dumped by `worldview parse`
+    Json,
+    /// A document tree, as dumped by `worldview parse --format yaml`
+    Yaml,
+    /// Headings and nested bullets, as written by `worldview export`
+    Markdown,
+}
+
+pub fn run(file: Option<PathBuf>, from: ImportFormat, output: Option<PathBuf>, skip_unmapped: bool) -> Result<()> {
+    let (name, input) = match &file {
+        Some(path) => (
+            path.display().to_string(),
+            std::fs::read_to_string(path).with_context(|| format!("Error reading {}", path.display()))?,
+        ),
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            ("<stdin>".to_string(), input)
+        }
+    };
+
+    let document: Document = match from {
+        ImportFormat::Json => serde_json::from_str(&input).with_context(|| format!("Invalid JSON in {}", name))?,
+        ImportFormat::Yaml => serde_yaml::from_str(&input).with_context(|| format!("Invalid YAML in {}", name))?,
+        ImportFormat::Markdown => {
+            let (document, unmapped) = from_markdown(&input);
+            for item in &unmapped {
+                eprintln!("{}: cannot map {}", name, item);
+            }
+            if !unmapped.is_empty() && !skip_unmapped {
+                eprintln!("Nothing written: edit these lines, or pass --skip-unmapped to leave them out");
+                std::process::exit(1);
+            }
+            document
+        }
+    };
+
+    // Validate the generated text as if it had been written by hand
+    let formatted = match import_tree(&document) {
+        Ok(formatted) => formatted,
+        Err(TreeError::Invalid { text, .. }) => {
+            let color = io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+            let path = output.as_ref().map_or_else(|| "<output>".to_string(), |p| p.display().to_string());
+            eprint!("{}", Renderer::new().color(color).render(&validate(&text), &text, &path));
+            eprintln!("Nothing written: the imported document is invalid");
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("{}: {}", name, e);
+            eprintln!("Nothing written: the imported document cannot be written as it stands");
+            std::process::exit(1);
+        }
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, formatted)?;
+            println!("Imported {} into {}", name, path.display());
+        }
+        None => print!("{}", formatted),
+    }
+    Ok(())
+}
@@ -3,12 +3,34 @@
 //! Commands:
 //!   validate  - Validate .wvf files for syntax errors
 //!   add       - Add facts to a Worldview file using an AI agent
+//!   explain   - Explain a diagnostic code such as WVF0013
+//!   fmt       - Rewrite .wvf files in canonical form
+//!   fix       - Apply automatic fixes for diagnostics
+//!   conformance - Check fixtures against expected diagnostics
+//!   parse     - Dump the parsed tree as JSON or YAML
+//!   import    - Write a .wvf file from a JSON or YAML tree, or a Markdown outline
+//!   export    - Render a .wvf file as Markdown
+//!   expand    - Render every claim as an English sentence
+//!
+//! Commands that take files default to the workspace members listed in the
+//! nearest `worldview.toml` (see [`manifest`]).
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use manifest::{DEFAULT_FILE, DEFAULT_MODEL, Manifest};
 use std::path::PathBuf;
 
 mod add;
+mod conformance;
+mod expand;
+mod explain;
+mod export;
+mod fix;
+mod fmt;
+mod import;
+mod manifest;
+mod parse;
+mod report;
 mod validate;
 
 /// CLI for working with Worldview format files
@@ -26,13 +48,24 @@ struct Cli {
 enum Commands {
     /// Validate Worldview files for syntax errors (also runs automatically on `add`)
     Validate {
-        /// Files to validate
-        #[arg(required_unless_present = "stdin")]
+        /// Files to validate (directories are searched for .wvf files);
+        /// defaults to the workspace members, validated as one workspace
         files: Vec<PathBuf>,
 
         /// Read from stdin instead of files
         #[arg(long)]
         stdin: bool,
+
+        /// Validate the files as one workspace, resolving references across them
+        #[arg(short, long, conflicts_with = "stdin")]
+        workspace: bool,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = report::OutputFormat::Human)]
+        format: report::OutputFormat,
+
+        #[command(flatten)]
+        lints: validate::LintArgs,
     },
 
     /// Add a fact to a Worldview file using an AI agent
@@ -41,26 +74,159 @@ enum Commands {
         #[arg(required = true)]
         fact: String,
 
-        /// Path to the Worldview file to modify
-        #[arg(short, long, default_value = "worldview.wvf")]
-        file: PathBuf,
+        /// Path to the Worldview file to modify [default: the workspace default, or worldview.wvf]
+        #[arg(short, long)]
+        file: Option<PathBuf>,
 
-        /// Model to use
-        #[arg(short, long, default_value = "claude-sonnet-4-20250514")]
-        model: String,
+        /// Model to use [default: the workspace agent model, or claude-sonnet-4-20250514]
+        #[arg(short, long)]
+        model: Option<String>,
 
         /// Enable verbose output
         #[arg(short, long)]
         verbose: bool,
     },
+
+    /// Explain a diagnostic code (e.g. WVF0013), or list all codes
+    Explain {
+        /// The diagnostic code to explain
+        code: Option<String>,
+    },
+
+    /// Rewrite Worldview files in canonical form
+    Fmt {
+        /// Files to format in place (default: the workspace members)
+        files: Vec<PathBuf>,
+
+        /// Read from stdin and write the formatted document to stdout
+        #[arg(long)]
+        stdin: bool,
+
+        /// Report files that are not formatted instead of rewriting them
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Apply automatic fixes for diagnostics, in place
+    Fix {
+        /// Files to fix (default: the workspace members)
+        files: Vec<PathBuf>,
+
+        /// Show the changes as a unified diff instead of writing them
+        #[arg(long)]
+        diff: bool,
+
+        /// Also apply fixes that may change meaning (e.g. moving a modifier)
+        #[arg(long = "unsafe")]
+        include_unsafe: bool,
+    },
+
+    /// Check .wvf fixtures against their .expected diagnostics files
+    Conformance {
+        /// Directory of fixtures, searched recursively
+        dir: PathBuf,
+
+        /// Rewrite the .expected files from this validator's diagnostics
+        #[arg(long)]
+        bless: bool,
+    },
+
+    /// Dump the parsed tree of Worldview files, with spans and diagnostics
+    Parse {
+        /// Files to parse (default: the workspace members)
+        files: Vec<PathBuf>,
+
+        /// Read from stdin instead of files
+        #[arg(long)]
+        stdin: bool,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = parse::DumpFormat::Json)]
+        format: parse::DumpFormat,
+    },
+
+    /// Write a Worldview file from a JSON or YAML document tree, or a Markdown outline
+    Import {
+        /// File to read (default: stdin)
+        file: Option<PathBuf>,
+
+        /// Input format
+        #[arg(long, value_enum)]
+        from: import::ImportFormat,
+
+        /// Write the document to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Leave out Markdown lines that cannot be mapped instead of failing
+        #[arg(long)]
+        skip_unmapped: bool,
+    },
+
+    /// Render a Worldview file for readers who do not know the notation
+    Export {
+        /// File to export (default: stdin)
+        file: Option<PathBuf>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = export::ExportFormat::Markdown)]
+        to: export::ExportFormat,
+
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Render every claim as an English sentence, for readers who do not know the notation
+    Expand {
+        /// Files to expand (default: the workspace members)
+        files: Vec<PathBuf>,
+
+        /// Read from stdin instead of files
+        #[arg(long)]
+        stdin: bool,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    // Only subcommands that act on the workspace need its manifest to be valid
     match cli.command {
-        Commands::Validate { files, stdin } => validate::run(files, stdin),
-        Commands::Add { fact, file, model, verbose } => add::run(fact, file, model, verbose).await,
+        Commands::Validate { files, stdin, workspace, format, lints } => {
+            validate::run(files, stdin, workspace, format, lints, Manifest::discover()?.as_ref())
+        }
+        Commands::Add { fact, file, model, verbose } => {
+            // Command-line flags win over the manifest, which wins over the built-in defaults
+            let manifest = Manifest::discover_or_warn();
+            let agent = manifest.as_ref().map(|m| &m.agent);
+            let file = file
+                .or_else(|| manifest.as_ref().map(Manifest::default_file))
+                .unwrap_or_else(|| DEFAULT_FILE.into());
+            let model = model
+                .or_else(|| agent.and_then(|a| a.model.clone()))
+                .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+            let verbose = verbose || agent.and_then(|a| a.verbose).unwrap_or(false);
+            add::run(fact, file, model, verbose).await
+        }
+        Commands::Explain { code } => explain::run(code),
+        Commands::Fmt { files, stdin, check } => {
+            // The manifest only supplies the default files
+            let manifest = if files.is_empty() && !stdin { Manifest::discover()? } else { None };
+            fmt::run(files, stdin, check, manifest.as_ref())
+        }
+        Commands::Fix { files, diff, include_unsafe } => {
+            fix::run(files, diff, include_unsafe, Manifest::discover()?.as_ref())
+        }
+        Commands::Conformance { dir, bless } => conformance::run(dir, bless),
+        Commands::Parse { files, stdin, format } => parse::run(files, stdin, format, Manifest::discover()?.as_ref()),
+        Commands::Import { file, from, output, skip_unmapped } => import::run(file, from, output, skip_unmapped),
+        Commands::Export { file, to, output } => {
+            // Only a file can be a workspace member, validated with the rest of the workspace
+            let manifest = if file.is_some() { Manifest::discover()? } else { None };
+            export::run(file, to, output, manifest.as_ref())
+        }
+        Commands::Expand { files, stdin } => expand::run(files, stdin, Manifest::discover()?.as_ref()),
     }
 }
@@ -0,0 +1,197 @@
+//! Workspace manifest - `worldview.toml`, discovered by walking up from the current directory
+//!
+//! ```toml
+//! [workspace]
+//! members = ["beliefs/*.wvf", "people.wvf"]   # default: every .wvf file below the manifest
+//! default = "beliefs/main.wvf"                # target of `worldview add`
+//!
+//! [lints]
+//! allow = ["standalone-modifier"]             # lists of lints by level
+//! missing-source = "deny"                     # or one lint at a time
+//! long-claim = { level = "warn", max-words = 20 }
+//!
+//! [rules]
+//! house-style = "rules/house-style.rhai"      # lint rules written as Rhai scripts
+//!
+//! [agent]
+//! model = "claude-sonnet-4-20250514"
+//! ```
+//!
+//! Paths are relative to the directory containing the manifest.
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use worldview_validator::lint::{self, Level, LintConfig};
+use worldview_validator::script::ScriptRule;
+
+/// File name of the workspace manifest
+pub const MANIFEST_NAME: &str = "worldview.toml";
+
+/// Document `worldview add` edits when neither `--file` nor a manifest names one
+pub const DEFAULT_FILE: &str = "worldview.wvf";
+
+/// Model `worldview add` uses when neither `--model` nor a manifest names one
+pub const DEFAULT_MODEL: &str = "claude-sonnet-4-20250514";
+
+/// A parsed `worldview.toml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default)]
+    pub workspace: WorkspaceSection,
+    /// Lint levels and options, as written (see [`Manifest::lints`])
+    #[serde(default, rename = "lints")]
+    lint_table: BTreeMap<String, toml::Value>,
+    /// Script rules, by name
+    #[serde(default)]
+    pub rules: BTreeMap<String, PathBuf>,
+    #[serde(default)]
+    pub agent: AgentSection,
+    /// Directory containing the manifest, relative to the current directory
+    #[serde(skip)]
+    pub root: PathBuf,
+    /// The `[lints]` section
+    #[serde(skip)]
+    pub lints: LintConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkspaceSection {
+    /// Member files or glob patterns
+    pub members: Option<Vec<String>>,
+    /// The document `worldview add` edits
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AgentSection {
+    pub model: Option<String>,
+    pub verbose: Option<bool>,
+}
+
+impl Manifest {
+    /// Find the nearest `worldview.toml` in the current directory or one of its parents
+    pub fn discover() -> Result<Option<Manifest>> {
+        let cwd = std::env::current_dir()?;
+        // Keep the path relative, so member paths print the way the user would type them
+        let mut relative = PathBuf::new();
+        for dir in cwd.ancestors() {
+            if dir.join(MANIFEST_NAME).is_file() {
+                return Manifest::load(&relative.join(MANIFEST_NAME)).map(Some);
+            }
+            relative.push("..");
+        }
+        Ok(None)
+    }
+
+    /// [`Manifest::discover`] for subcommands that can do without a manifest:
+    /// one that cannot be read is reported and ignored
+    pub fn discover_or_warn() -> Option<Manifest> {
+        Manifest::discover().unwrap_or_else(|e| {
+            eprintln!("Warning: continuing without the workspace manifest: {}", format!("{:#}", e).trim_end());
+            None
+        })
+    }
+
+    /// Read and check a manifest
+    pub fn load(path: &Path) -> Result<Manifest> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Error reading {}", path.display()))?;
+        let mut manifest: Manifest = toml::from_str(&text).with_context(|| format!("Invalid {}", path.display()))?;
+        manifest.root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        manifest.lints = lint_config(&manifest.lint_table).with_context(|| format!("Invalid [lints] in {}", path.display()))?;
+        if let Some(name) = manifest.rules.keys().find(|name| lint::lookup(name).is_some()) {
+            bail!("Invalid [rules] in {}: '{}' is a built-in lint", path.display(), name);
+        }
+        Ok(manifest)
+    }
+
+    /// The workspace member files, in sorted order
+    pub fn members(&self) -> Result<Vec<PathBuf>> {
+        let patterns = match &self.workspace.members {
+            Some(members) => members.clone(),
+            None => vec!["**/*.wvf".to_string()],
+        };
+        let root = glob::Pattern::escape(&self.root.to_string_lossy());
+        let mut members = Vec::new();
+        for pattern in &patterns {
+            let full = if root.is_empty() { pattern.clone() } else { format!("{}/{}", root, pattern) };
+            let before = members.len();
+            for path in glob::glob(&full).with_context(|| format!("Invalid member pattern '{}'", pattern))? {
+                members.push(path?);
+            }
+            if members.len() == before && self.workspace.members.is_some() {
+                bail!("{}: member '{}' matches no files", MANIFEST_NAME, pattern);
+            }
+        }
+        members.sort();
+        members.dedup();
+        Ok(members)
+    }
+
+    /// Compile the `[rules]` scripts
+    pub fn script_rules(&self) -> Result<Vec<ScriptRule>> {
+        self.rules
+            .iter()
+            .map(|(name, path)| Ok(ScriptRule::load(name, &self.root.join(path))?))
+            .collect()
+    }
+
+    /// The document `worldview add` edits
+    pub fn default_file(&self) -> PathBuf {
+        self.root.join(self.workspace.default.as_deref().unwrap_or(DEFAULT_FILE))
+    }
+}
+
+/// Read the `[lints]` section: lists by level first, then single lints, which win
+fn lint_config(table: &BTreeMap<String, toml::Value>) -> Result<LintConfig> {
+    let mut config = LintConfig::new();
+    for level in [Level::Allow, Level::Warn, Level::Deny] {
+        let Some(list) = table.get(&level.to_string()) else { continue };
+        let Some(names) = list.as_array() else {
+            bail!("'{}' must be a list of lints", level);
+        };
+        for name in names {
+            let Some(name) = name.as_str() else {
+                bail!("the entries of '{}' must be lint names", level);
+            };
+            config.set_level(name, level)?;
+        }
+    }
+
+    for (name, value) in table {
+        if name.parse::<Level>().is_ok() {
+            continue;
+        }
+        match value {
+            toml::Value::String(level) => config.set_level(name, level.parse()?)?,
+            toml::Value::Table(section) => {
+                for (key, value) in section {
+                    match (key.as_str(), value) {
+                        ("level", toml::Value::String(level)) => config.set_level(name, level.parse()?)?,
+                        ("max-words", toml::Value::Integer(max)) if name == "long-claim" => {
+                            config.max_claim_words = usize::try_from(*max)?;
+                        }
+                        _ => bail!("unknown option '{}' for lint '{}'", key, name),
+                    }
+                }
+            }
+            _ => bail!("'{}' must be a level or a table", name),
+        }
+    }
+    Ok(config)
+}
+
+/// The files a subcommand acts on: those given, or else the manifest's members
+pub fn files_or_members(files: Vec<PathBuf>, manifest: Option<&Manifest>) -> Result<Vec<PathBuf>> {
+    if !files.is_empty() {
+        return Ok(files);
+    }
+    match manifest {
+        Some(manifest) => manifest.members(),
+        None => bail!("No files given, and no {} found in this directory or any parent", MANIFEST_NAME),
+    }
+}
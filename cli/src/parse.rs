@@ -0,0 +1,68 @@
+//! Parse subcommand - dumps the parsed tree of .wvf files as JSON or YAML
+//!
+//! The output is described by `spec/parse.schema.json`.
+
+use crate::manifest::{Manifest, files_or_members};
+use crate::validate::{validate_documents, validator};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use worldview_validator::ValidationResult;
+use worldview_validator::workspace::WorkspaceFile;
+
+/// Output format for `worldview parse`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DumpFormat {
+    Json,
+    Yaml,
+}
+
+/// The dump of every input
+#[derive(Serialize)]
+struct Dump {
+    files: Vec<FileDump>,
+}
+
+/// One input: its path, parsed document and diagnostics
+#[derive(Serialize)]
+struct FileDump {
+    path: String,
+    #[serde(flatten)]
+    result: ValidationResult,
+}
+
+pub fn run(files: Vec<PathBuf>, stdin: bool, format: DumpFormat, manifest: Option<&Manifest>) -> Result<()> {
+    let validator = validator(manifest.map(|m| m.lints.clone()).unwrap_or_default(), manifest)?;
+    let mut dump = Dump { files: Vec::new() };
+
+    if stdin {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        dump.files.push(FileDump {
+            path: "<stdin>".to_string(),
+            result: validator.validate(&content),
+        });
+    } else {
+        // Without explicit files, the manifest's members are checked as one workspace
+        let workspace = files.is_empty();
+        let mut documents = Vec::new();
+        for path in &files_or_members(files, manifest)? {
+            documents.push(WorkspaceFile::read(path).with_context(|| format!("Error reading {}", path.display()))?);
+        }
+        let results = validate_documents(&validator, &documents, workspace);
+        for (document, result) in documents.iter().zip(results) {
+            dump.files.push(FileDump {
+                path: document.path.display().to_string(),
+                result,
+            });
+        }
+    }
+
+    match format {
+        DumpFormat::Json => println!("{}", serde_json::to_string_pretty(&dump)?),
+        DumpFormat::Yaml => print!("{}", serde_yaml::to_string(&dump)?),
+    }
+    Ok(())
+}
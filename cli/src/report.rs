@@ -0,0 +1,469 @@
+//! Machine-readable validation reports: JSON, SARIF, GitHub annotations and JUnit XML
+
+use clap::ValueEnum;
+use serde_json::{Value, json};
+use worldview_validator::fix::{Applicability, Fix};
+use worldview_validator::{Severity, Span, ValidationResult, codes};
+
+/// Output format for `worldview validate`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Source-annotated diagnostics for people
+    Human,
+    /// A JSON document with every diagnostic
+    Json,
+    /// SARIF 2.1.0, for code scanning
+    Sarif,
+    /// GitHub Actions workflow commands (PR annotations)
+    Github,
+    /// JUnit XML, one test case per file
+    Junit,
+}
+
+/// The validation result for one input, with the source it was computed from
+pub struct FileReport {
+    pub path: String,
+    pub source: String,
+    pub result: ValidationResult,
+}
+
+impl FileReport {
+    /// 1-based column one past the last character of `span`
+    fn end_column(&self, span: Span) -> usize {
+        let end = span.end.min(self.source.len());
+        let start = span.start.min(end);
+        span.column + self.source[start..end].chars().count()
+    }
+}
+
+/// Render all reports in a machine-readable format
+pub fn render(format: OutputFormat, reports: &[FileReport]) -> String {
+    match format {
+        OutputFormat::Human => unreachable!("human output is rendered per file"),
+        OutputFormat::Json => to_json(reports),
+        OutputFormat::Sarif => to_sarif(reports),
+        OutputFormat::Github => to_github(reports),
+        OutputFormat::Junit => to_junit(reports),
+    }
+}
+
+fn span_json(report: &FileReport, span: Span) -> Value {
+    json!({
+        "line": span.line,
+        "column": span.column,
+        "end_line": span.line,
+        "end_column": report.end_column(span),
+        "start": span.start,
+        "end": span.end,
+    })
+}
+
+fn to_json(reports: &[FileReport]) -> String {
+    let files: Vec<Value> = reports
+        .iter()
+        .map(|report| {
+            let diagnostics: Vec<Value> = report
+                .result
+                .diagnostics()
+                .into_iter()
+                .map(|(severity, d)| {
+                    let labels: Vec<Value> = d
+                        .labels()
+                        .into_iter()
+                        .map(|l| json!({ "message": l.message, "span": span_json(report, l.span) }))
+                        .collect();
+                    json!({
+                        "code": d.code(),
+                        "severity": severity.to_string(),
+                        "message": d.message(),
+                        "span": span_json(report, d.span()),
+                        "labels": labels,
+                        "fix": d.fix(&report.source).map(|fix| fix_json(report, &fix)),
+                    })
+                })
+                .collect();
+            json!({
+                "path": report.path,
+                "valid": report.result.is_valid(),
+                "errors": report.result.errors.len(),
+                "warnings": report.result.warnings.len(),
+                "diagnostics": diagnostics,
+            })
+        })
+        .collect();
+
+    pretty(&json!({ "files": files }))
+}
+
+fn fix_json(report: &FileReport, fix: &Fix) -> Value {
+    let edits: Vec<Value> = fix
+        .edits
+        .iter()
+        .map(|e| json!({ "span": span_json(report, e.span), "replacement": e.replacement }))
+        .collect();
+    json!({
+        "message": fix.message,
+        "applicability": match fix.applicability {
+            Applicability::MachineApplicable => "machine-applicable",
+            Applicability::MaybeIncorrect => "maybe-incorrect",
+        },
+        "edits": edits,
+    })
+}
+
+/// SARIF result level for a severity
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info | Severity::Hint => "note",
+    }
+}
+
+fn sarif_region(report: &FileReport, span: Span) -> Value {
+    json!({
+        "startLine": span.line,
+        "startColumn": span.column,
+        "endLine": span.line,
+        "endColumn": report.end_column(span),
+    })
+}
+
+fn sarif_location(report: &FileReport, span: Span) -> Value {
+    json!({
+        "physicalLocation": {
+            "artifactLocation": { "uri": report.path.replace('\\', "/") },
+            "region": sarif_region(report, span),
+        }
+    })
+}
+
+fn sarif_fix(report: &FileReport, fix: &Fix) -> Value {
+    let replacements: Vec<Value> = fix
+        .edits
+        .iter()
+        .map(|e| {
+            json!({
+                "deletedRegion": sarif_region(report, e.span),
+                "insertedContent": { "text": e.replacement },
+            })
+        })
+        .collect();
+    json!({
+        "description": { "text": fix.message },
+        "artifactChanges": [{
+            "artifactLocation": { "uri": report.path.replace('\\', "/") },
+            "replacements": replacements,
+        }],
+    })
+}
+
+fn to_sarif(reports: &[FileReport]) -> String {
+    let rules: Vec<Value> = codes::CODES
+        .iter()
+        .map(|c| {
+            json!({
+                "id": c.code,
+                "name": c.name,
+                "shortDescription": { "text": c.summary() },
+                "fullDescription": { "text": c.explanation },
+                "defaultConfiguration": { "level": sarif_level(c.severity) },
+            })
+        })
+        .collect();
+
+    let results: Vec<Value> = reports
+        .iter()
+        .flat_map(|report| {
+            report.result.diagnostics().into_iter().map(move |(severity, d)| {
+                let related: Vec<Value> = d
+                    .labels()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(id, l)| {
+                        let mut location = sarif_location(report, l.span);
+                        location["id"] = json!(id);
+                        location["message"] = json!({ "text": l.message });
+                        location
+                    })
+                    .collect();
+                let mut result = json!({
+                    "ruleId": d.code(),
+                    "level": sarif_level(severity),
+                    "message": { "text": d.message() },
+                    "locations": [sarif_location(report, d.span())],
+                });
+                if let Some(index) = codes::CODES.iter().position(|c| c.code == d.code()) {
+                    result["ruleIndex"] = json!(index);
+                }
+                if !related.is_empty() {
+                    result["relatedLocations"] = json!(related);
+                }
+                if let Some(fix) = d.fix(&report.source) {
+                    result["fixes"] = json!([sarif_fix(report, &fix)]);
+                }
+                result
+            })
+        })
+        .collect();
+
+    pretty(&json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "worldview",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            // Columns are counted in characters, not the default UTF-16 code units
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }]
+    }))
+}
+
+/// Escape a GitHub workflow command message
+fn github_escape_data(text: &str) -> String {
+    text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Escape a GitHub workflow command property value
+fn github_escape_property(text: &str) -> String {
+    github_escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+fn to_github(reports: &[FileReport]) -> String {
+    let mut output = String::new();
+    for report in reports {
+        for (severity, d) in report.result.diagnostics() {
+            let command = match severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+                Severity::Info | Severity::Hint => "notice",
+            };
+            let span = d.span();
+            output.push_str(&format!(
+                "::{} file={},line={},col={},endColumn={},title={}::{}\n",
+                command,
+                github_escape_property(&report.path),
+                span.line,
+                span.column,
+                report.end_column(span),
+                github_escape_property(d.code()),
+                github_escape_data(&d.message()),
+            ));
+        }
+    }
+    output
+}
+
+/// Escape text for XML attributes and content
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn to_junit(reports: &[FileReport]) -> String {
+    let failures = reports.iter().filter(|r| !r.result.is_valid()).count();
+    let mut output = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    output.push_str(&format!(
+        "<testsuites name=\"worldview\" tests=\"{}\" failures=\"{}\">\n",
+        reports.len(),
+        failures
+    ));
+    output.push_str(&format!(
+        "  <testsuite name=\"worldview validate\" tests=\"{}\" failures=\"{}\">\n",
+        reports.len(),
+        failures
+    ));
+
+    for report in reports {
+        let path = xml_escape(&report.path);
+        let lines: Vec<String> = report
+            .result
+            .diagnostics()
+            .into_iter()
+            .map(|(severity, d)| {
+                let span = d.span();
+                format!(
+                    "{}:{}:{}: {}[{}]: {}",
+                    report.path,
+                    span.line,
+                    span.column,
+                    severity,
+                    d.code(),
+                    d.message()
+                )
+            })
+            .collect();
+
+        output.push_str(&format!(
+            "    <testcase name=\"{}\" classname=\"worldview.validate\">\n",
+            path
+        ));
+        let first_error = report
+            .result
+            .diagnostics()
+            .into_iter()
+            .find_map(|(severity, d)| (severity == Severity::Error).then_some(d));
+        if let Some(first) = first_error {
+            let errors = report.result.errors.len();
+            output.push_str(&format!(
+                "      <failure message=\"{} error{}\" type=\"{}\">{}</failure>\n",
+                errors,
+                if errors == 1 { "" } else { "s" },
+                first.code(),
+                xml_escape(&lines.join("\n"))
+            ));
+        } else if !lines.is_empty() {
+            output.push_str(&format!(
+                "      <system-out>{}</system-out>\n",
+                xml_escape(&lines.join("\n"))
+            ));
+        }
+        output.push_str("    </testcase>\n");
+    }
+
+    output.push_str("  </testsuite>\n</testsuites>\n");
+    output
+}
+
+fn pretty(value: &Value) -> String {
+    let mut output = serde_json::to_string_pretty(value).expect("JSON values always serialize");
+    output.push('\n');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use worldview_validator::{ValidationError, validate};
+
+    /// An undefined reference after non-ASCII text, and a custom warning whose
+    /// message needs escaping
+    fn reports() -> Vec<FileReport> {
+        let source = "Cafe\n  .menu\n    - crème => brûlée &Cafe.menus\n".to_string();
+        let mut result = validate(&source);
+        let span = result.document.concepts[0].facets[0].claims[0].data.text_span;
+        result.warnings.push(ValidationError::Custom {
+            span,
+            code: "house-style".to_string(),
+            message: "first line\n<second> & 100%".to_string(),
+        });
+        vec![FileReport { path: "menu, draft.wvf".to_string(), source, result }]
+    }
+
+    fn parse(output: &str) -> Value {
+        serde_json::from_str(output).expect("Expected JSON output")
+    }
+
+    #[test]
+    fn test_end_column_counts_characters() {
+        let reports = reports();
+        let report = &reports[0];
+        let reference = &report.result.errors[0];
+        assert_eq!(reference.code(), "WVF0013");
+        // `&Cafe.menus` starts after 22 characters (25 bytes) of the line
+        assert_eq!(reference.span().column, 23);
+        assert_eq!(report.end_column(reference.span()), 34);
+    }
+
+    #[test]
+    fn test_json() {
+        let output = parse(&render(OutputFormat::Json, &reports()));
+        let file = &output["files"][0];
+        assert_eq!(file["path"], "menu, draft.wvf");
+        assert_eq!(file["valid"], false);
+        assert_eq!(file["errors"], 1);
+        assert_eq!(file["warnings"], 1);
+
+        let custom = &file["diagnostics"][0];
+        assert_eq!(custom["code"], "house-style");
+        assert_eq!(custom["message"], "first line\n<second> & 100%");
+        assert_eq!(
+            custom["span"],
+            json!({ "line": 3, "column": 7, "end_line": 3, "end_column": 22, "start": 19, "end": 37 })
+        );
+
+        let reference = &file["diagnostics"][1];
+        assert_eq!(reference["code"], "WVF0013");
+        assert_eq!(reference["severity"], "error");
+        assert_eq!(reference["span"]["end_column"], 34);
+        assert_eq!(
+            reference["fix"],
+            json!({
+                "message": "replace with '&Cafe.menu'",
+                "applicability": "maybe-incorrect",
+                "edits": [{
+                    "span": { "line": 3, "column": 23, "end_line": 3, "end_column": 34, "start": 38, "end": 49 },
+                    "replacement": "&Cafe.menu",
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_sarif() {
+        let output = parse(&render(OutputFormat::Sarif, &reports()));
+        assert_eq!(output["version"], "2.1.0");
+        let run = &output["runs"][0];
+        assert_eq!(run["columnKind"], "unicodeCodePoints");
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), codes::CODES.len());
+
+        // Custom codes are not among the driver's rules
+        let custom = &run["results"][0];
+        assert_eq!(custom["ruleId"], "house-style");
+        assert!(custom.get("ruleIndex").is_none());
+        assert_eq!(custom["message"]["text"], "first line\n<second> & 100%");
+
+        let reference = &run["results"][1];
+        let index = reference["ruleIndex"].as_u64().unwrap() as usize;
+        assert_eq!(reference["ruleId"], "WVF0013");
+        assert_eq!(run["tool"]["driver"]["rules"][index]["id"], "WVF0013");
+        assert_eq!(reference["level"], "error");
+        let region = json!({ "startLine": 3, "startColumn": 23, "endLine": 3, "endColumn": 34 });
+        let location = &reference["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "menu, draft.wvf");
+        assert_eq!(location["region"], region);
+
+        let fix = &reference["fixes"][0];
+        assert_eq!(fix["description"]["text"], "replace with '&Cafe.menu'");
+        let replacement = &fix["artifactChanges"][0]["replacements"][0];
+        assert_eq!(replacement["deletedRegion"], region);
+        assert_eq!(replacement["insertedContent"]["text"], "&Cafe.menu");
+    }
+
+    #[test]
+    fn test_github() {
+        assert_eq!(
+            render(OutputFormat::Github, &reports()),
+            "::warning file=menu%2C draft.wvf,line=3,col=7,endColumn=22,title=house-style::first line%0A<second> & 100%25\n\
+             ::error file=menu%2C draft.wvf,line=3,col=23,endColumn=34,title=WVF0013::undefined reference 'Cafe.menus' \
+             (no such concept.facet in document; did you mean 'Cafe.menu'?)\n"
+        );
+    }
+
+    #[test]
+    fn test_junit() {
+        assert_eq!(
+            render(OutputFormat::Junit, &reports()),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="worldview" tests="1" failures="1">
+  <testsuite name="worldview validate" tests="1" failures="1">
+    <testcase name="menu, draft.wvf" classname="worldview.validate">
+      <failure message="1 error" type="WVF0013">menu, draft.wvf:3:7: warning[house-style]: first line
+&lt;second&gt; &amp; 100%
+menu, draft.wvf:3:23: error[WVF0013]: undefined reference &apos;Cafe.menus&apos; (no such concept.facet in document; did you mean &apos;Cafe.menu&apos;?)</failure>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+}
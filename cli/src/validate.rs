@@ -1,50 +1,143 @@
 //! Validate subcommand - validates .wvf files for syntax errors
 
+use crate::manifest::{Manifest, files_or_members};
+use crate::report::{self, FileReport, OutputFormat};
 use anyhow::Result;
-use std::io::{self, Read};
-use std::path::PathBuf;
+use clap::Args;
+use std::io::{self, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use worldview_validator::lint::{Level, LintConfig};
+use worldview_validator::{ValidationResult, Validator};
+use worldview_validator::render::Renderer;
+use worldview_validator::workspace::{WorkspaceFile, find_documents};
 
-pub fn run(files: Vec<PathBuf>, stdin: bool) -> Result<()> {
+/// Lint levels given on the command line, applied over those in worldview.toml
+#[derive(Args, Debug)]
+pub struct LintArgs {
+    /// Allow a lint (by name, e.g. long-claim, or code)
+    #[arg(short = 'A', long = "allow", value_name = "LINT")]
+    allow: Vec<String>,
+
+    /// Report a lint as a warning
+    #[arg(short = 'W', long = "warn", value_name = "LINT")]
+    warn: Vec<String>,
+
+    /// Report a lint as an error
+    #[arg(short = 'D', long = "deny", value_name = "LINT")]
+    deny: Vec<String>,
+
+    /// Report every warning as an error
+    #[arg(long)]
+    deny_warnings: bool,
+}
+
+impl LintArgs {
+    /// The manifest's lint configuration with these overrides; `-D` wins over `-W`, which wins over `-A`
+    fn config(&self, manifest: Option<&Manifest>) -> Result<LintConfig> {
+        let mut config = manifest.map(|m| m.lints.clone()).unwrap_or_default();
+        for (lints, level) in [(&self.allow, Level::Allow), (&self.warn, Level::Warn), (&self.deny, Level::Deny)] {
+            for lint in lints {
+                config.set_level(lint, level)?;
+            }
+        }
+        config.deny_warnings |= self.deny_warnings;
+        Ok(config)
+    }
+}
+
+pub fn run(
+    files: Vec<PathBuf>,
+    stdin: bool,
+    workspace: bool,
+    format: OutputFormat,
+    lints: LintArgs,
+    manifest: Option<&Manifest>,
+) -> Result<()> {
+    let validator = validator(lints.config(manifest)?, manifest)?;
     let mut all_valid = true;
 
+    // Colour only when writing to a terminal, and never when NO_COLOR is set
+    let color = io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let renderer = Renderer::new().color(color);
+
+    // Machine-readable formats are emitted once, after every input is validated
+    let mut reports = Vec::new();
+    let mut emit = |path: String, source: String, result: ValidationResult| {
+        let valid = result.is_valid();
+        if format == OutputFormat::Human {
+            if !reports.is_empty() {
+                println!();
+            }
+            print!("{}", renderer.render(&result, &source, &path));
+        }
+        reports.push(FileReport { path, source, result });
+        valid
+    };
+
     if stdin {
         // Read from stdin
         let mut content = String::new();
         io::stdin().read_to_string(&mut content)?;
-        let result = worldview_validator::validate(&content);
-        print!("{}", result);
-        if !result.is_valid() {
-            all_valid = false;
-        }
+        let result = validator.validate(&content);
+        all_valid &= emit("<stdin>".to_string(), content, result);
     } else {
-        // Validate each file
-        for path in &files {
-            if files.len() > 1 {
-                println!("{}:", path.display());
-            }
-
-            match worldview_validator::validate_file(path) {
-                Ok(result) => {
-                    println!("{}", result);
-                    if !result.is_valid() {
-                        all_valid = false;
-                    }
-                }
+        // Without explicit files, the manifest's members are checked as one workspace
+        let workspace = workspace || files.is_empty();
+        let mut documents = Vec::new();
+        for path in &files_or_members(files, manifest)? {
+            match read_documents(path) {
+                Ok(files) => documents.extend(files),
                 Err(e) => {
                     eprintln!("Error reading {}: {}", path.display(), e);
                     all_valid = false;
                 }
             }
+        }
 
-            if files.len() > 1 {
-                println!();
-            }
+        let results = validate_documents(&validator, &documents, workspace);
+        for (document, result) in documents.into_iter().zip(results) {
+            all_valid &= emit(document.path.display().to_string(), document.source, result);
         }
     }
 
+    if format != OutputFormat::Human {
+        print!("{}", report::render(format, &reports));
+    }
+
     if all_valid {
         Ok(())
     } else {
         std::process::exit(1);
     }
 }
+
+/// A validator with the given lint levels and the manifest's script rules
+pub fn validator(lints: LintConfig, manifest: Option<&Manifest>) -> Result<Validator> {
+    let mut validator = Validator::new().lints(lints);
+    if let Some(manifest) = manifest {
+        for rule in manifest.script_rules()? {
+            validator = validator.rule(rule);
+        }
+    }
+    validator.check_lints()?;
+    Ok(validator)
+}
+
+/// Validate documents as one workspace, where references resolve across every
+/// file, or each on its own
+pub fn validate_documents(validator: &Validator, documents: &[WorkspaceFile], workspace: bool) -> Vec<ValidationResult> {
+    if workspace {
+        validator.validate_workspace(documents)
+    } else {
+        documents.iter().map(|d| validator.validate(&d.source)).collect()
+    }
+}
+
+/// Read a file, or every .wvf file under a directory
+fn read_documents(path: &Path) -> io::Result<Vec<WorkspaceFile>> {
+    if path.is_dir() {
+        find_documents(path)?.iter().map(|p| WorkspaceFile::read(p)).collect()
+    } else {
+        Ok(vec![WorkspaceFile::read(path)?])
+    }
+}
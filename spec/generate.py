@@ -106,6 +106,16 @@ def generate_language_spec(tokens: dict) -> str:
     output.append("```")
     output.append("")
 
+    # Escapes
+    esc = tokens["escape"]
+    chars = ", ".join(f"`{esc['symbol']}{c}`" for c in esc["characters"])
+    output.append("### Escapes\n")
+    output.append(f"A backslash makes the next character literal text rather than a marker: {chars}\n")
+    output.append("```")
+    output.append(esc["example"])
+    output.append("```")
+    output.append("")
+
     return "\n".join(output)
 
 
@@ -154,6 +164,22 @@ def generate_rust_constants(tokens: dict) -> str:
     output.append("];")
     output.append("")
 
+    # Escapes
+    output.append("/// Escape character, and the characters it makes literal")
+    output.append(f"pub const ESCAPE: char = '{tokens['escape']['symbol']}';".replace("'\\'", "'\\\\'"))
+    output.append("pub const ESCAPABLE: &[char] = &[" + ", ".join(
+        "'\\\\'" if c == "\\" else f"'{c}'" for c in tokens["escape"]["characters"]) + "];")
+    output.append("")
+
+    # Positional grammar order
+    output.append("/// Claim elements in positional grammar order, with their prefix (`None` for the claim text)")
+    output.append("pub const CLAIM_ORDER: &[(&str, Option<char>)] = &[")
+    for element in tokens["claim_order"]:
+        prefix = f"Some('{element['prefix']}')" if element.get("prefix") else "None"
+        output.append(f'    ("{element["element"]}", {prefix}),')
+    output.append("];")
+    output.append("")
+
     # Inline element symbols
     output.append("/// Inline element symbols")
     output.append("pub const CONDITION_SYMBOL: char = '|';")
@@ -389,6 +415,8 @@ def generate_system_prompt(tokens: dict) -> str:
     output.append("")
     output.append("Every concept has facets. Every facet has claims. Claims may include conditions, sources, and references.")
     output.append("")
+    output.append("Concept, facet and source names use only letters, digits, `-` and `_` (`Human-nature`, not `Human nature`).")
+    output.append("")
 
     # Notation table
     output.append("## Notation")
@@ -422,6 +450,9 @@ def generate_system_prompt(tokens: dict) -> str:
     output.append("")
     output.append("Position implies role. No labels needed.")
     output.append("")
+    esc = tokens["escape"]
+    output.append(f"Escape a literal `|`, `@`, `&` or `[` with a backslash: `{esc['example']}`")
+    output.append("")
 
     # Maintenance rules
     output.append("## Maintenance Rules")
@@ -485,11 +516,11 @@ def update_readme(tokens: dict) -> str:
     if old_start_marker in readme_content:
         # Replace old diagram section with new spec section
         pattern = f"{re.escape(old_start_marker)}.*?{re.escape(old_end_marker)}"
-        readme_content = re.sub(pattern, new_section, readme_content, flags=re.DOTALL)
+        readme_content = re.sub(pattern, lambda _: new_section, readme_content, flags=re.DOTALL)
     elif start_marker in readme_content:
         # Replace existing spec section
         pattern = f"{re.escape(start_marker)}.*?{re.escape(end_marker)}"
-        readme_content = re.sub(pattern, new_section, readme_content, flags=re.DOTALL)
+        readme_content = re.sub(pattern, lambda _: new_section, readme_content, flags=re.DOTALL)
     else:
         # Insert before "## Tools" section
         tools_marker = "## Tools"
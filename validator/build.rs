@@ -10,6 +10,8 @@ use std::path::Path;
 struct TokenSpec {
     brief_forms: Vec<BriefForm>,
     modifiers: Vec<Modifier>,
+    escape: Escape,
+    claim_order: Vec<ClaimElement>,
 }
 
 #[derive(Deserialize)]
@@ -24,6 +26,18 @@ struct Modifier {
     meaning: String,
 }
 
+#[derive(Deserialize)]
+struct Escape {
+    symbol: char,
+    characters: Vec<char>,
+}
+
+#[derive(Deserialize)]
+struct ClaimElement {
+    element: String,
+    prefix: Option<char>,
+}
+
 fn main() {
     let out_dir = env::var("OUT_DIR").unwrap();
     let dest_path = Path::new(&out_dir).join("tokens.rs");
@@ -61,6 +75,23 @@ fn generate_tokens_rs(spec: &TokenSpec) -> String {
     for m in &spec.modifiers {
         output.push_str(&format!("    (\"{}\", \"{}\"),\n", m.symbol, m.meaning));
     }
+    output.push_str("];\n\n");
+
+    // Generate ESCAPE and ESCAPABLE
+    output.push_str("/// Escape character defined in the Worldview spec\n");
+    output.push_str(&format!("pub const ESCAPE: char = {:?};\n\n", spec.escape.symbol));
+    output.push_str("/// Characters that the escape character makes literal\n");
+    output.push_str("pub const ESCAPABLE: &[char] = &[");
+    let chars: Vec<String> = spec.escape.characters.iter().map(|c| format!("{:?}", c)).collect();
+    output.push_str(&chars.join(", "));
+    output.push_str("];\n\n");
+
+    // Generate CLAIM_ORDER
+    output.push_str("/// Claim elements in positional grammar order, with their prefix (`None` for the claim text)\n");
+    output.push_str("pub const CLAIM_ORDER: &[(&str, Option<char>)] = &[\n");
+    for e in &spec.claim_order {
+        output.push_str(&format!("    (\"{}\", {:?}),\n", e.element, e.prefix));
+    }
     output.push_str("];\n");
 
     output
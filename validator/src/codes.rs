@@ -0,0 +1,130 @@
+//! Stable diagnostic codes and their long-form explanations
+//!
+//! Every [`ValidationError`](crate::ValidationError) has a code such as `WVF0013`.
+//! Codes are never renumbered or reused, so they are safe to cite in reviews
+//! and allow-lists. Explanations live in `src/codes/<CODE>.md`.
+
+use crate::Severity;
+
+/// A registered diagnostic code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiagnosticCode {
+    /// The stable identifier, e.g. `WVF0013`
+    pub code: &'static str,
+    /// The diagnostic's name (its `ValidationError` variant)
+    pub name: &'static str,
+    /// Default severity
+    pub severity: Severity,
+    /// Long description with erroneous and corrected examples (Markdown)
+    pub explanation: &'static str,
+}
+
+impl DiagnosticCode {
+    /// One-line summary: the first line of the explanation
+    pub fn summary(&self) -> &'static str {
+        self.explanation.lines().next().unwrap_or("")
+    }
+}
+
+macro_rules! codes {
+    ($($code:literal => $name:ident, $severity:ident;)*) => {
+        /// Every diagnostic code, in numeric order
+        pub const CODES: &[DiagnosticCode] = &[
+            $(DiagnosticCode {
+                code: $code,
+                name: stringify!($name),
+                severity: Severity::$severity,
+                explanation: include_str!(concat!("codes/", $code, ".md")),
+            },)*
+        ];
+    };
+}
+
+codes! {
+    "WVF0001" => InvalidIndentation, Error;
+    "WVF0002" => MissingFacetPrefix, Error;
+    "WVF0003" => MissingClaimPrefix, Error;
+    "WVF0004" => ConceptWithoutFacets, Error;
+    "WVF0005" => FacetWithoutClaims, Error;
+    "WVF0006" => OrphanFacet, Error;
+    "WVF0007" => OrphanClaim, Error;
+    "WVF0008" => EmptyClaimText, Error;
+    "WVF0009" => UnexpectedIndentation, Error;
+    "WVF0010" => EmptyConceptName, Error;
+    "WVF0011" => EmptyFacetName, Error;
+    "WVF0012" => InvalidReferenceFormat, Error;
+    "WVF0013" => UndefinedReference, Error;
+    "WVF0014" => EmptyCondition, Error;
+    "WVF0015" => EmptySource, Error;
+    "WVF0016" => EmptyReference, Error;
+    "WVF0017" => BriefFormMissingLeftOperand, Error;
+    "WVF0018" => BriefFormMissingRightOperand, Error;
+    "WVF0019" => UnclosedEvolutionMarker, Error;
+    "WVF0020" => EmptyEvolutionMarker, Error;
+    "WVF0021" => MalformedEvolutionMarker, Error;
+    "WVF0022" => StandaloneModifier, Warning;
+    "WVF0023" => DuplicateConcept, Error;
+    "WVF0024" => DuplicateFacet, Error;
+    "WVF0025" => DuplicateClaim, Warning;
+    "WVF0026" => UnescapedMarker, Error;
+    "WVF0027" => MisplacedClaimElement, Error;
+    "WVF0028" => UnreferenceableConcept, Warning;
+    "WVF0029" => InvalidIdentifier, Error;
+    "WVF0030" => GrammarMismatch, Error;
+    "WVF0031" => CrossFileDuplicateConcept, Error;
+    "WVF0032" => LongClaim, Warning;
+    "WVF0033" => MissingSource, Warning;
+    "WVF0034" => InvalidDirective, Error;
+    "WVF0035" => UnknownLint, Warning;
+    "WVF0036" => ScriptRuleFailed, Error;
+    "WVF0037" => UnstableFormat, Error;
+}
+
+/// Look up a diagnostic code
+///
+/// Accepts the canonical form (`WVF0013`) as well as shorthands such as
+/// `wvf13` or `13`.
+pub fn lookup(code: &str) -> Option<&'static DiagnosticCode> {
+    let code = code.trim();
+    let digits = match code.get(..3) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("WVF") => &code[3..],
+        _ => code,
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let number: u32 = digits.parse().ok()?;
+    let canonical = format!("WVF{:04}", number);
+    CODES.iter().find(|c| c.code == canonical)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_are_sequential() {
+        for (idx, code) in CODES.iter().enumerate() {
+            assert_eq!(code.code, format!("WVF{:04}", idx + 1));
+        }
+    }
+
+    #[test]
+    fn test_explanations_have_examples() {
+        for code in CODES {
+            assert!(!code.summary().is_empty(), "{} has no summary", code.code);
+            assert!(code.explanation.contains("```wvf"), "{} has no example", code.code);
+        }
+    }
+
+    #[test]
+    fn test_lookup_accepts_shorthand() {
+        assert_eq!(lookup("WVF0013").unwrap().name, "UndefinedReference");
+        assert_eq!(lookup("wvf13").unwrap().code, "WVF0013");
+        assert_eq!(lookup("13").unwrap().code, "WVF0013");
+        assert!(lookup("WVF9999").is_none());
+        assert!(lookup("E0308").is_none());
+        assert!(lookup("+13").is_none());
+        assert!(lookup("wvf+13").is_none());
+    }
+}
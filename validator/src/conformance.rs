@@ -0,0 +1,183 @@
+//! Conformance checking against expected-diagnostics files
+//!
+//! A conformance fixture is a `.wvf` document paired with a `.expected` file
+//! listing the diagnostics it must produce, one per line as `CODE LINE:COLUMN`:
+//!
+//! ```text
+//! # an unclosed evolution marker
+//! WVF0019 3:16
+//! ```
+//!
+//! Lines and columns are 1-based, and columns count characters (see
+//! [`Span`](crate::Span)). Blank lines and `#` comments are ignored, and
+//! order does not matter. Any implementation of the format can be checked
+//! against the same fixtures; [`expectations`] gives this validator's answer.
+
+use crate::{ValidationResult, validate};
+use std::fmt;
+use thiserror::Error;
+
+/// A diagnostic a fixture is expected to produce: its code and where it starts
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Expectation {
+    pub line: usize,
+    pub column: usize,
+    pub code: String,
+}
+
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}:{}", self.code, self.line, self.column)
+    }
+}
+
+/// A malformed line in an expected-diagnostics file
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("line {line}: expected 'CODE LINE:COLUMN', found '{text}'")]
+pub struct ParseExpectationError {
+    pub line: usize,
+    pub text: String,
+}
+
+/// Parse an expected-diagnostics file
+pub fn parse_expectations(text: &str) -> Result<Vec<Expectation>, ParseExpectationError> {
+    let mut expectations = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = || ParseExpectationError {
+            line: idx + 1,
+            text: line.to_string(),
+        };
+        let (code, position) = line.split_once(char::is_whitespace).ok_or_else(error)?;
+        let (row, column) = position.trim().split_once(':').ok_or_else(error)?;
+        expectations.push(Expectation {
+            line: row.parse().map_err(|_| error())?,
+            column: column.parse().map_err(|_| error())?,
+            code: code.to_string(),
+        });
+    }
+    Ok(expectations)
+}
+
+/// The diagnostics in a validation result, errors and warnings alike, in document order
+pub fn expectations(result: &ValidationResult) -> Vec<Expectation> {
+    let mut expectations: Vec<_> = result
+        .errors
+        .iter()
+        .chain(&result.warnings)
+        .map(|d| Expectation {
+            line: d.span().line,
+            column: d.span().column,
+            code: d.code().to_string(),
+        })
+        .collect();
+    expectations.sort();
+    expectations
+}
+
+/// Render expectations as an expected-diagnostics file
+pub fn render_expectations(expectations: &[Expectation]) -> String {
+    expectations.iter().map(|e| format!("{}\n", e)).collect()
+}
+
+/// How actual diagnostics differ from the expected ones
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Expected but not produced
+    pub missing: Vec<Expectation>,
+    /// Produced but not expected
+    pub unexpected: Vec<Expectation>,
+}
+
+impl Mismatch {
+    /// Returns true if the diagnostics matched exactly
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+/// Compare expected and actual diagnostics, ignoring order
+///
+/// A diagnostic expected twice must be produced twice.
+pub fn compare(expected: &[Expectation], actual: &[Expectation]) -> Mismatch {
+    let mut unexpected = actual.to_vec();
+    let mut missing = Vec::new();
+    for expectation in expected {
+        match unexpected.iter().position(|a| a == expectation) {
+            Some(idx) => {
+                unexpected.remove(idx);
+            }
+            None => missing.push(expectation.clone()),
+        }
+    }
+    missing.sort();
+    unexpected.sort();
+    Mismatch { missing, unexpected }
+}
+
+/// Validate a fixture and compare its diagnostics with the expected ones
+pub fn check(source: &str, expected: &[Expectation]) -> Mismatch {
+    compare(expected, &expectations(&validate(source)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{fixture_dir, fixtures};
+
+    fn expect(code: &str, line: usize, column: usize) -> Expectation {
+        Expectation {
+            line,
+            column,
+            code: code.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_expectations() {
+        let text = "# comment\n\nWVF0019 3:16\n  WVF0022   4:7  \n";
+        assert_eq!(
+            parse_expectations(text).unwrap(),
+            [expect("WVF0019", 3, 16), expect("WVF0022", 4, 7)]
+        );
+        assert_eq!(
+            parse_expectations("WVF0019 3\n").unwrap_err(),
+            ParseExpectationError {
+                line: 1,
+                text: "WVF0019 3".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_render_round_trips() {
+        let result = validate("Power\n  .core\n    - ^ absolute [<= relative\n");
+        let actual = expectations(&result);
+        assert_eq!(render_expectations(&actual), "WVF0022 3:7\nWVF0019 3:18\n");
+        assert_eq!(parse_expectations(&render_expectations(&actual)).unwrap(), actual);
+    }
+
+    #[test]
+    fn test_compare_counts_duplicates() {
+        let twice = [expect("WVF0014", 3, 5), expect("WVF0014", 3, 5)];
+        let mismatch = compare(&twice, &twice[..1]);
+        assert_eq!(mismatch.missing, [expect("WVF0014", 3, 5)]);
+        assert!(mismatch.unexpected.is_empty());
+        assert!(compare(&twice, &twice).is_empty());
+    }
+
+    #[test]
+    fn test_fixture_corpus_conforms() {
+        for kind in ["valid", "invalid", "semantic"] {
+            for (name, source) in fixtures(kind) {
+                let path = fixture_dir(kind).join(&name).with_extension("expected");
+                let expected = std::fs::read_to_string(&path).expect("Expected an .expected file");
+                let mismatch = check(&source, &parse_expectations(&expected).unwrap());
+                assert!(mismatch.is_empty(), "{kind}/{name}: {mismatch:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Plain-English rendering of claims, for readers who do not know the notation
+//!
+//! The rendering is template-based and deterministic, using the meanings of the
+//! brief forms and modifiers in `spec/tokens.yaml`:
+//!
+//! - a brief form reads as a verb phrase between its operands, which read as
+//!   noun phrases (`=>`, "causes, leads to", reads "leads to"; `vs` reads "is in
+//!   tension with")
+//! - a modifier reads as the first phrase of its meaning, before the word it is
+//!   attached to (`concentration^` reads "increasing concentration"; stacked
+//!   modifiers are separated by commas), or in parentheses after a word it is
+//!   separated from (`fast !` reads "fast (strong)")
+//! - conditions follow as "when ...", and sources, references and the prior
+//!   belief in parentheses
+//!
+//! So `concentration^ => abuse^ @historical-pattern` reads "Increasing
+//! concentration leads to increasing abuse (basis: historical-pattern)".
+
+use crate::lexer::{TokenKind, lex_claim, unescape};
+use crate::{ClaimData, Document, MODIFIERS, Span};
+
+/// Each brief form as a verb phrase, drawn from its meaning in `spec/tokens.yaml`
+const OPERATOR_PHRASES: &[(&str, &str)] = &[
+    ("=>", "leads to"),
+    ("~", "resembles"),
+    ("=", "means"),
+    ("vs", "is in tension with"),
+];
+
+/// Render a document as indented English sentences, one per claim
+pub fn expand_document(document: &Document) -> String {
+    let mut output = String::new();
+    for (i, concept) in document.concepts.iter().enumerate() {
+        if i > 0 {
+            output.push('\n');
+        }
+        output.push_str(&concept.name);
+        output.push('\n');
+        for facet in &concept.facets {
+            output.push_str(&format!("  {}\n", facet.name));
+            for claim in &facet.claims {
+                output.push_str(&format!("    - {}\n", expand_claim(&claim.data)));
+            }
+        }
+    }
+    output
+}
+
+/// Render a claim as an English sentence
+pub fn expand_claim(claim: &ClaimData) -> String {
+    let mut sentence = capitalize(&expand_text(&claim.text));
+    if !claim.conditions.is_empty() {
+        let conditions: Vec<String> = claim.conditions.iter().map(|c| expand_text(c)).collect();
+        sentence.push_str(" when ");
+        sentence.push_str(&conditions.join(" and "));
+    }
+
+    let mut notes = Vec::new();
+    if !claim.sources.is_empty() {
+        let sources: Vec<&str> = claim.sources.iter().map(|s| s.as_str()).collect();
+        notes.push(format!("basis: {}", sources.join(", ")));
+    }
+    if !claim.references.is_empty() {
+        let references: Vec<String> = claim.references.iter().map(|r| r.to_string()).collect();
+        notes.push(format!("see {}", references.join(", ")));
+    }
+    if let Some(evolution) = &claim.evolution {
+        notes.push(format!("supersedes: {}", expand_text(&evolution.prior_belief)));
+    }
+    if !notes.is_empty() {
+        sentence.push_str(&format!(" ({})", notes.join("; ")));
+    }
+    sentence
+}
+
+/// Claim text or a condition with its operators and modifiers in words
+fn expand_text(text: &str) -> String {
+    let mut words: Vec<String> = Vec::new();
+    // End of the last word, while a modifier could still be attached to it
+    let mut word_end = None;
+    // Whether the last word already has a modifier before it
+    let mut modified = false;
+    for token in lex_claim(text, Span::default()) {
+        match token.kind {
+            TokenKind::Modifier => {
+                let meaning = modifier_meaning(token.text);
+                match words.last_mut() {
+                    Some(word) if word_end == Some(token.span.start) => {
+                        let separator = if modified { ", " } else { " " };
+                        *word = format!("{}{}{}", meaning, separator, word);
+                        word_end = Some(token.span.end);
+                        modified = true;
+                    }
+                    _ => {
+                        words.push(format!("({})", meaning));
+                        word_end = None;
+                    }
+                }
+                continue;
+            }
+            TokenKind::Operator => words.push(operator_meaning(token.text).to_string()),
+            _ => words.push(unescape(token.text).into_owned()),
+        }
+        word_end = (token.kind == TokenKind::Word).then_some(token.span.end);
+        modified = false;
+    }
+    words.join(" ")
+}
+
+/// A brief form as a verb phrase, e.g. `leads to` for `=>`
+fn operator_meaning(operator: &str) -> &str {
+    OPERATOR_PHRASES
+        .iter()
+        .find(|(symbol, _)| *symbol == operator)
+        .map_or(operator, |(_, phrase)| phrase)
+}
+
+/// The first phrase of a modifier's meaning, e.g. `increasing` for `^`
+fn modifier_meaning(modifier: &str) -> &str {
+    MODIFIERS
+        .iter()
+        .find(|(symbol, _)| *symbol == modifier)
+        .and_then(|(_, meaning)| meaning.split(", ").next())
+        .unwrap_or(modifier)
+}
+
+/// Upper-case the first character, as at the start of a sentence
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn expand(claim: &str) -> String {
+        let document = parse(&format!("Power\n  .core\n    - {}\n", claim));
+        expand_claim(&document.claims().next().expect("Expected a claim").2.data)
+    }
+
+    #[test]
+    fn test_expand_brief_forms_and_modifiers() {
+        assert_eq!(
+            expand("concentration^ => abuse^ @historical-pattern"),
+            "Increasing concentration leads to increasing abuse (basis: historical-pattern)"
+        );
+        assert_eq!(expand("authority ~ influence"), "Authority resembles influence");
+        assert_eq!(expand("efficiency vs thoroughness"), "Efficiency is in tension with thoroughness");
+        assert_eq!(expand("free-will?*"), "Notable, uncertain free-will");
+        assert_eq!(expand("free-will? *"), "Uncertain free-will (notable)");
+    }
+
+    #[test]
+    fn test_every_brief_form_has_a_phrase() {
+        for (symbol, _) in crate::BRIEF_FORMS {
+            assert_ne!(operator_meaning(symbol), *symbol, "{} has no phrase", symbol);
+        }
+    }
+
+    #[test]
+    fn test_expand_standalone_modifiers() {
+        assert_eq!(expand("fast !"), "Fast (strong)");
+        assert_eq!(expand("trust v"), "Trust (decreasing)");
+    }
+
+    #[test]
+    fn test_expand_inline_elements() {
+        assert_eq!(
+            expand("corrupts | unchecked | concentration^ @history @Acton &Power.core [<= neutral]"),
+            "Corrupts when unchecked and increasing concentration (basis: history, Acton; see Power.core; supersedes: neutral)"
+        );
+        assert_eq!(expand(r"AT\&T dominates"), "AT&T dominates");
+    }
+
+    #[test]
+    fn test_expand_document() {
+        let document = parse("Power\n  .core\n    - corrupts\n\nTrust\n  .formation\n    - slow\n    - consistency => loyalty\n");
+        assert_eq!(
+            expand_document(&document),
+            "Power\n  core\n    - Corrupts\n\nTrust\n  formation\n    - Slow\n    - Consistency leads to loyalty\n"
+        );
+    }
+}
@@ -0,0 +1,543 @@
+//! Machine-applicable fixes for diagnostics
+//!
+//! A [`Fix`] is a set of [`TextEdit`]s against the source a diagnostic was
+//! produced from. Fixes marked [`Applicability::MachineApplicable`] are safe to
+//! apply without review; [`fix_source`] applies them until the document stops
+//! changing, and [`Validator::fix_workspace`] until none of a workspace's files do.
+
+use crate::workspace::WorkspaceFile;
+use crate::{Span, ValidationError, ValidationResult, Validator};
+
+/// How confident a fix is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Applicability {
+    /// Probably what was meant, but it changes the meaning of the text; review it
+    MaybeIncorrect,
+    /// Definitely what was meant; safe to apply automatically
+    MachineApplicable,
+}
+
+/// Replace the text at `span` (empty for an insertion) with `replacement`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub span: Span,
+    pub replacement: String,
+}
+
+/// A suggested change that resolves a diagnostic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    /// What the fix does, e.g. "add the '.' facet prefix"
+    pub message: String,
+    /// Non-overlapping edits, in source order
+    pub edits: Vec<TextEdit>,
+    pub applicability: Applicability,
+}
+
+impl Fix {
+    fn new(message: impl Into<String>, edits: Vec<TextEdit>, applicability: Applicability) -> Self {
+        Self {
+            message: message.into(),
+            edits,
+            applicability,
+        }
+    }
+
+    /// Returns true if the fix can be applied without review
+    pub fn is_safe(&self) -> bool {
+        self.applicability == Applicability::MachineApplicable
+    }
+}
+
+impl ValidationError {
+    /// A fix for this diagnostic, if there is an unambiguous mechanical one
+    ///
+    /// `source` must be the text the diagnostic was produced from.
+    pub fn fix(&self, source: &str) -> Option<Fix> {
+        use Applicability::*;
+
+        match self {
+            ValidationError::InvalidIndentation { span, .. } => {
+                let content = rest_of_line(source, span.end);
+                let (indent, kind) = indent_for(content)?;
+                let content = Span { start: span.end, ..*span };
+                Some(Fix::new(
+                    format!("indent the {} by {} spaces", kind, indent),
+                    vec![replace(*span, " ".repeat(indent))],
+                    if has_place(source, content, kind) { MachineApplicable } else { MaybeIncorrect },
+                ))
+            }
+            ValidationError::MissingFacetPrefix { span } => {
+                // A claim at facet depth is under-indented rather than unprefixed
+                if source[span.start..].starts_with('-') {
+                    return Some(reindent(source, *span, 4, "claim"));
+                }
+                Some(Fix::new(
+                    "add the '.' facet prefix",
+                    vec![insert(source, *span, span.start, ".")],
+                    MachineApplicable,
+                ))
+            }
+            ValidationError::MissingClaimPrefix { span } => {
+                // A facet at claim depth is over-indented rather than unprefixed
+                if source[span.start..].starts_with('.') {
+                    return Some(reindent(source, *span, 2, "facet"));
+                }
+                Some(Fix::new(
+                    "add the '-' claim prefix",
+                    vec![insert(source, *span, span.start, "- ")],
+                    MachineApplicable,
+                ))
+            }
+            ValidationError::UnclosedEvolutionMarker { span } => Some(Fix::new(
+                "close the evolution marker",
+                vec![insert(source, *span, span.end, "]")],
+                MachineApplicable,
+            )),
+            ValidationError::UnescapedMarker { span, marker } => Some(Fix::new(
+                format!("escape the '{}' as literal text", marker),
+                vec![insert(source, *span, span.start, "\\")],
+                MachineApplicable,
+            )),
+            ValidationError::MisplacedClaimElement { claim, .. } => {
+                let (reordered, moves_text) = crate::reorder_claim(&source[claim.start..claim.end], *claim);
+                Some(Fix::new(
+                    "put the claim elements in order",
+                    vec![replace(*claim, reordered)],
+                    if moves_text { MaybeIncorrect } else { MachineApplicable },
+                ))
+            }
+            ValidationError::UnreferenceableConcept { span, concept } => {
+                let name = identifier_for(concept)?;
+                Some(Fix::new(
+                    format!("rename to '{}'", name),
+                    vec![replace(*span, name)],
+                    MaybeIncorrect,
+                ))
+            }
+            ValidationError::InvalidIdentifier { span, name, .. } => {
+                // The name ends the span; a facet span also covers its '.' prefix
+                let suggestion = identifier_for(name)?;
+                let name_span = Span {
+                    end: span.end,
+                    ..point(source, *span, span.end - name.len())
+                };
+                Some(Fix::new(
+                    format!("rename to '{}'", suggestion),
+                    vec![replace(name_span, suggestion)],
+                    MaybeIncorrect,
+                ))
+            }
+            ValidationError::StandaloneModifier { span, modifier } => {
+                // Only a modifier leading the claim has an obvious target: the next term
+                let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
+                if source[line_start..span.start].trim() != "-" {
+                    return None;
+                }
+                let after = &source[span.end..];
+                let term_start = span.end + (after.len() - after.trim_start().len());
+                let term = rest_of_line(source, term_start).split_whitespace().next()?;
+                if term.starts_with(['|', '@', '&', '[']) || crate::BRIEF_FORMS.iter().any(|(op, _)| term == *op) {
+                    return None;
+                }
+                let term_end = term_start + term.len();
+                Some(Fix::new(
+                    format!("attach '{}' to '{}'", modifier, term),
+                    vec![
+                        replace(Span { end: term_start, ..*span }, ""),
+                        insert(source, *span, term_end, modifier),
+                    ],
+                    MaybeIncorrect,
+                ))
+            }
+            ValidationError::UndefinedReference { span, suggestions, reference } => {
+                let best = suggestions.first()?;
+                // A unique match differing only in case is certainly the intended target
+                let applicability = if suggestions.len() == 1 && best.eq_ignore_ascii_case(reference) {
+                    MachineApplicable
+                } else {
+                    MaybeIncorrect
+                };
+                Some(Fix::new(
+                    format!("replace with '&{}'", best),
+                    vec![replace(*span, format!("&{}", best))],
+                    applicability,
+                ))
+            }
+            ValidationError::DuplicateClaim { span, .. } => {
+                // Remove the whole line, including its line ending
+                let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
+                let line_end = source[span.end..].find('\n').map_or(source.len(), |i| span.end + i + 1);
+                let line = Span {
+                    line: span.line,
+                    column: 1,
+                    start: line_start,
+                    end: line_end,
+                };
+                Some(Fix::new("remove the repeated claim", vec![replace(line, "")], MachineApplicable))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The result of [`fix_source`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixOutcome {
+    /// The fixed source text
+    pub source: String,
+    /// Every fix that was applied, across all passes
+    pub applied: Vec<Fix>,
+}
+
+/// Upper bound on validate/apply rounds, in case fixes keep producing new ones
+const MAX_PASSES: usize = 10;
+
+/// Apply fixes to `source` with the built-in rules until no more apply
+///
+/// Only safe fixes are applied unless `include_unsafe` is set. Each round
+/// re-validates the output, since one fix can expose another diagnostic.
+pub fn fix_source(source: &str, include_unsafe: bool) -> FixOutcome {
+    Validator::new().fix_source(source, include_unsafe)
+}
+
+impl Validator {
+    /// Apply fixes to a single document, like [`fix_source`]
+    pub fn fix_source(&self, source: &str, include_unsafe: bool) -> FixOutcome {
+        let file = WorkspaceFile {
+            path: Default::default(),
+            source: source.to_string(),
+        };
+        let mut outcomes = self.fix_workspace(&[file], include_unsafe);
+        outcomes.remove(0)
+    }
+
+    /// Apply fixes to documents validated together, one outcome per file
+    ///
+    /// References are checked, and corrected, against the concepts of every
+    /// file (see [`Validator::validate_workspace`]).
+    pub fn fix_workspace(&self, files: &[WorkspaceFile], include_unsafe: bool) -> Vec<FixOutcome> {
+        let mut files = files.to_vec();
+        let mut applied = vec![Vec::new(); files.len()];
+
+        for _ in 0..MAX_PASSES {
+            let results = self.validate_workspace(&files);
+            let mut changed = false;
+            for ((file, result), applied) in files.iter_mut().zip(results).zip(&mut applied) {
+                let fixes = fixes_for(&result, &file.source, include_unsafe);
+                let (fixed, used) = apply_fixes(&file.source, &fixes);
+                if !used.is_empty() {
+                    file.source = fixed;
+                    applied.extend(used);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        files
+            .into_iter()
+            .zip(applied)
+            .map(|(file, applied)| FixOutcome {
+                source: file.source,
+                applied,
+            })
+            .collect()
+    }
+}
+
+/// The fixes for every diagnostic in `result`, only safe ones unless `include_unsafe` is set
+fn fixes_for(result: &ValidationResult, source: &str, include_unsafe: bool) -> Vec<Fix> {
+    result
+        .errors
+        .iter()
+        .chain(&result.warnings)
+        .filter_map(|d| d.fix(source))
+        .filter(|f| include_unsafe || f.is_safe())
+        .collect()
+}
+
+/// Apply as many of `fixes` as possible in one pass
+///
+/// A fix whose edits overlap an earlier fix's edits is skipped; it can be
+/// retried against the new text. Returns the new text and the fixes applied.
+pub fn apply_fixes(source: &str, fixes: &[Fix]) -> (String, Vec<Fix>) {
+    let mut accepted: Vec<&Fix> = Vec::new();
+
+    for fix in fixes {
+        let in_bounds = fix.edits.iter().all(|e| e.span.start <= e.span.end && e.span.end <= source.len());
+        let conflicts = accepted
+            .iter()
+            .flat_map(|f| &f.edits)
+            .any(|taken| fix.edits.iter().any(|edit| conflict(taken.span, edit.span)));
+        if in_bounds && !conflicts {
+            accepted.push(fix);
+        }
+    }
+
+    let mut edits: Vec<&TextEdit> = accepted.iter().flat_map(|f| &f.edits).collect();
+    edits.sort_by_key(|e| (e.span.start, e.span.end));
+
+    let mut output = String::with_capacity(source.len());
+    let mut last = 0;
+    for edit in edits {
+        output.push_str(&source[last..edit.span.start]);
+        output.push_str(&edit.replacement);
+        last = edit.span.end;
+    }
+    output.push_str(&source[last..]);
+
+    (output, accepted.into_iter().cloned().collect())
+}
+
+/// Edits conflict if they overlap, or insert at the same point (ambiguous order)
+fn conflict(a: Span, b: Span) -> bool {
+    (a.start < b.end && b.start < a.end) || (a.is_empty() && b.is_empty() && a.start == b.start)
+}
+
+/// The expected indent for a line's content, and what kind of line it is
+fn indent_for(content: &str) -> Option<(usize, &'static str)> {
+    if content.starts_with('.') {
+        Some((2, "facet"))
+    } else if content.starts_with('-') {
+        Some((4, "claim"))
+    } else {
+        None
+    }
+}
+
+/// Whether the line at `content`, indented as a `kind`, would fit in the tree:
+/// a facet needs a name, and a claim a facet above it
+fn has_place(source: &str, content: Span, kind: &str) -> bool {
+    if kind == "facet" {
+        return !rest_of_line(source, content.start)[1..].trim().is_empty();
+    }
+    let line_start = source[..content.start].rfind('\n').map_or(0, |i| i + 1);
+    source[..line_start]
+        .lines()
+        .rev()
+        .map(str::trim_end)
+        .find(|line| !line.is_empty() && !line.trim_start().starts_with('-'))
+        .and_then(|line| line.strip_prefix("  ."))
+        .is_some_and(|name| !name.trim().is_empty())
+}
+
+/// Replace the indentation before `content` with `indent` spaces
+///
+/// The fix is safe only when the line would fit in the tree once moved.
+fn reindent(source: &str, content: Span, indent: usize, kind: &str) -> Fix {
+    let line_start = source[..content.start].rfind('\n').map_or(0, |i| i + 1);
+    let indentation = Span {
+        line: content.line,
+        column: 1,
+        start: line_start,
+        end: content.start,
+    };
+    Fix::new(
+        format!("indent the {} by {} spaces", kind, indent),
+        vec![replace(indentation, " ".repeat(indent))],
+        if has_place(source, content, kind) { Applicability::MachineApplicable } else { Applicability::MaybeIncorrect },
+    )
+}
+
+/// Text from `offset` to the end of its line, without the line ending
+fn rest_of_line(source: &str, offset: usize) -> &str {
+    let rest = &source[offset..];
+    let line = rest.split('\n').next().unwrap_or("");
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+/// An empty span at `offset`, at or after `anchor` on the same line
+fn point(source: &str, anchor: Span, offset: usize) -> Span {
+    Span {
+        line: anchor.line,
+        column: anchor.column + source[anchor.start..offset].chars().count(),
+        start: offset,
+        end: offset,
+    }
+}
+
+/// `name` made into an identifier by joining its words with '-', if that is enough
+fn identifier_for(name: &str) -> Option<String> {
+    let joined = name.split_whitespace().collect::<Vec<_>>().join("-");
+    crate::is_identifier(&joined).then_some(joined)
+}
+
+fn insert(source: &str, anchor: Span, offset: usize, text: &str) -> TextEdit {
+    replace(point(source, anchor, offset), text)
+}
+
+fn replace(span: Span, replacement: impl Into<String>) -> TextEdit {
+    TextEdit {
+        span,
+        replacement: replacement.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate;
+
+    fn fixed(input: &str) -> String {
+        fix_source(input, false).source
+    }
+
+    #[test]
+    fn test_fix_indentation() {
+        assert_eq!(fixed("Power\n   .core\n    - corrupts\n"), "Power\n  .core\n    - corrupts\n");
+        assert_eq!(fixed("Power\n  .core\n   - corrupts\n"), "Power\n  .core\n    - corrupts\n");
+        assert_eq!(fixed("Power\n  .core\n  - corrupts\n"), "Power\n  .core\n    - corrupts\n");
+        assert_eq!(fixed("Power\n    .core\n    - corrupts\n"), "Power\n  .core\n    - corrupts\n");
+    }
+
+    #[test]
+    fn test_ambiguous_indentation_has_no_fix() {
+        let input = "Power\n  .core\n   corrupts\n";
+        let result = validate(input);
+        assert!(result.errors[0].fix(input).is_none());
+        assert_eq!(fixed(input), input);
+    }
+
+    #[test]
+    fn test_reindent_without_a_place_is_unsafe() {
+        // A claim with no facet above it, and a facet without a name
+        for input in ["A\n  - c\n", "A\n  .b\n    - c\n    . \n", "A\n   - c\n"] {
+            let result = validate(input);
+            let fix = result.errors[0].fix(input).expect("Expected a fix");
+            assert!(!fix.is_safe(), "{:?}", input);
+            assert_eq!(fixed(input), input);
+        }
+    }
+
+    #[test]
+    fn test_fix_missing_prefixes() {
+        assert_eq!(fixed("Power\n  core\n    - corrupts\n"), "Power\n  .core\n    - corrupts\n");
+        assert_eq!(fixed("Power\n  .core\n    corrupts\n"), "Power\n  .core\n    - corrupts\n");
+    }
+
+    #[test]
+    fn test_fix_unclosed_evolution_marker() {
+        assert_eq!(
+            fixed("Mind\n  .view\n    - adaptive [<= inherently good @history\n"),
+            "Mind\n  .view\n    - adaptive @history [<= inherently good]\n"
+        );
+    }
+
+    #[test]
+    fn test_fix_unescaped_markers() {
+        assert_eq!(
+            fixed("Market\n  .telecom\n    - AT&T dominates | contact ops@corp\n"),
+            "Market\n  .telecom\n    - AT\\&T dominates | contact ops\\@corp\n"
+        );
+    }
+
+    #[test]
+    fn test_fix_claim_order() {
+        assert_eq!(
+            fixed("Power\n  .core\n    - corrupts &Trust @history | unchecked [<= absolute]\nTrust\n  .core\n    - slow\n"),
+            "Power\n  .core\n    - corrupts | unchecked @history &Trust [<= absolute]\nTrust\n  .core\n    - slow\n"
+        );
+        assert_eq!(
+            fixed("Power\n  .core\n    - corrupts | unchecked [<= absolute] | alone\n"),
+            "Power\n  .core\n    - corrupts | unchecked | alone [<= absolute]\n"
+        );
+    }
+
+    #[test]
+    fn test_moving_claim_text_is_unsafe() {
+        let input = "Power\n  .core\n    - corrupts @history mostly\n";
+        let result = validate(input);
+        let fix = result.errors[0].fix(input).expect("Expected a fix");
+        assert_eq!(fix.applicability, Applicability::MaybeIncorrect);
+        assert_eq!(apply_fixes(input, &[fix]).0, "Power\n  .core\n    - corrupts mostly @history\n");
+    }
+
+    #[test]
+    fn test_fix_names() {
+        let input = "Human nature\n  .social life\n    - cooperative @field-notes,\n";
+        let result = validate(input);
+        let fixes: Vec<_> = result.errors.iter().chain(&result.warnings).filter_map(|e| e.fix(input)).collect();
+        assert_eq!(fixes.len(), 2, "the source name has no obvious fix");
+        assert!(fixes.iter().all(|fix| fix.applicability == Applicability::MaybeIncorrect));
+        assert_eq!(apply_fixes(input, &fixes).0, "Human-nature\n  .social-life\n    - cooperative @field-notes,\n");
+    }
+
+    #[test]
+    fn test_modifier_fix_is_unsafe() {
+        let input = "Power\n  .core\n    - ^ concentration\n";
+        let result = validate(input);
+        let fix = result.warnings[0].fix(input).expect("Expected a fix");
+        assert_eq!(fix.applicability, Applicability::MaybeIncorrect);
+        assert_eq!(fix.message, "attach '^' to 'concentration'");
+
+        assert_eq!(fixed(input), input);
+        assert_eq!(fix_source(input, true).source, "Power\n  .core\n    - concentration^\n");
+    }
+
+    #[test]
+    fn test_fix_reference_case() {
+        let input = "Trust\n  .formation\n    - slow &trust.formation &Trust.formaton\n";
+        assert_eq!(fixed(input), "Trust\n  .formation\n    - slow &Trust.formation &Trust.formaton\n");
+        assert_eq!(
+            fix_source(input, true).source,
+            "Trust\n  .formation\n    - slow &Trust.formation &Trust.formation\n"
+        );
+    }
+
+    #[test]
+    fn test_fix_workspace_references() {
+        let file = |path: &str, source: &str| WorkspaceFile {
+            path: path.into(),
+            source: source.to_string(),
+        };
+        let files = [
+            file("ethics.wvf", "Honesty\n  .practice\n    - builds trust &trust.formation &Trust\n"),
+            file("people.wvf", "Trust\n  .formation\n    - slow &Honesty\n"),
+        ];
+
+        // Alone, neither file defines the other's concepts
+        assert!(fix_source(&files[0].source, true).applied.is_empty());
+
+        let outcomes = Validator::new().fix_workspace(&files, false);
+        assert_eq!(outcomes[0].source, "Honesty\n  .practice\n    - builds trust &Trust.formation &Trust\n");
+        assert!(outcomes[1].applied.is_empty());
+    }
+
+    #[test]
+    fn test_fix_duplicate_claim() {
+        let input = "Trust\n  .formation\n    - slow\n    - contextual\n    -  slow\n    - slow";
+        assert_eq!(fixed(input), "Trust\n  .formation\n    - slow\n    - contextual\n");
+    }
+
+    #[test]
+    fn test_fix_edit_spans() {
+        let input = "Power\n  core\n    - corrupts\n";
+        let result = validate(input);
+        let fix = result.errors[0].fix(input).unwrap();
+        let span = fix.edits[0].span;
+        assert_eq!((span.line, span.column, span.start, span.end), (2, 3, 8, 8));
+    }
+
+    #[test]
+    fn test_fixes_compose_across_passes() {
+        // The claim is only checked once its facet is back in the tree
+        let outcome = fix_source("Power\n   .core\n    - adaptive [<= good\n", false);
+        assert_eq!(outcome.source, "Power\n  .core\n    - adaptive [<= good]\n");
+        assert_eq!(outcome.applied.len(), 2);
+        assert!(validate(&outcome.source).is_valid());
+    }
+
+    #[test]
+    fn test_conflicting_fixes_are_skipped() {
+        let span = |start, end| Span { line: 1, column: start + 1, start, end };
+        let fix = |span, text: &str| Fix::new("", vec![replace(span, text)], Applicability::MachineApplicable);
+        let fixes = [fix(span(0, 0), "a"), fix(span(0, 0), "b"), fix(span(1, 3), "c"), fix(span(2, 2), "d")];
+
+        let (output, applied) = apply_fixes("xyz", &fixes);
+        assert_eq!(output, "axc");
+        assert_eq!(applied.len(), 2);
+    }
+}
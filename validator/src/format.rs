@@ -0,0 +1,494 @@
+//! Canonical pretty-printer for Worldview documents
+//!
+//! The canonical form is:
+//!
+//! - any allowed lints in one `#![allow(...)]` directive, then a blank line
+//! - concepts at column 0, separated by exactly one blank line
+//! - facets as `  .name`, claims as `    - text`
+//! - inline elements in positional order: `| condition`, `@source`, `&reference`,
+//!   then `[<= prior belief]`, each preceded by a single space
+//! - single spaces inside text and around symbolic brief forms (`=>`, `~`, `=`)
+//! - no trailing whitespace, and a single newline at the end of the file
+//!
+//! Claim order within a facet is never changed, since it carries implicit evolution.
+
+use crate::lexer::{Token, TokenKind, escape, lex_claim, unescape};
+use crate::{ClaimData, Document, Span, ValidationError, parse, validate};
+use thiserror::Error;
+
+/// Format Worldview source text
+///
+/// Documents with errors that lose text are refused, and those errors returned:
+/// lines the parser cannot place in the tree, and claim elements it cannot read,
+/// would otherwise be dropped. Errors about a complete tree, such as undefined
+/// references and duplicates, and warnings do not prevent formatting. A
+/// document whose formatted text would read back as a different tree is
+/// refused with an `UnstableFormat` error.
+pub fn format_source(source: &str) -> Result<String, Vec<ValidationError>> {
+    let result = validate(source);
+    let errors: Vec<ValidationError> = result.errors.into_iter().filter(loses_text).collect();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let formatted = format_document(&result.document);
+    match difference(&stated(&result.document), &stated(&parse(&formatted))) {
+        Some((_, span)) => Err(vec![ValidationError::UnstableFormat { span }]),
+        None => Ok(formatted),
+    }
+}
+
+/// Whether formatting the tree would drop or rewrite text the error points at
+fn loses_text(error: &ValidationError) -> bool {
+    !matches!(
+        error,
+        ValidationError::ConceptWithoutFacets { .. }
+            | ValidationError::FacetWithoutClaims { .. }
+            | ValidationError::UndefinedReference { .. }
+            | ValidationError::DuplicateConcept { .. }
+            | ValidationError::DuplicateFacet { .. }
+            | ValidationError::DuplicateClaim { .. }
+            | ValidationError::CrossFileDuplicateConcept { .. }
+            | ValidationError::UnreferenceableConcept { .. }
+            | ValidationError::InvalidIdentifier { .. }
+            | ValidationError::LongClaim { .. }
+            | ValidationError::MissingSource { .. }
+            | ValidationError::UnknownLint { .. }
+    )
+}
+
+/// Render a document tree in canonical form
+pub fn format_document(document: &Document) -> String {
+    let mut output = String::new();
+    if !document.allow.is_empty() {
+        let lints: Vec<&str> = document.allow.iter().map(|lint| lint.value.as_str()).collect();
+        output.push_str(&format!("#![allow({})]\n\n", lints.join(", ")));
+    }
+    for (i, concept) in document.concepts.iter().enumerate() {
+        if i > 0 {
+            output.push('\n');
+        }
+        output.push_str(&collapse_whitespace(&concept.name));
+        output.push('\n');
+        for facet in &concept.facets {
+            output.push_str("  .");
+            output.push_str(&collapse_whitespace(&facet.name));
+            output.push('\n');
+            for claim in &facet.claims {
+                output.push_str("    ");
+                output.push_str(&format_claim(&claim.data));
+                output.push('\n');
+            }
+        }
+    }
+    output
+}
+
+/// Why a document built outside the parser cannot be written as source text
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum TreeError {
+    #[error("{kind} '{}' contains a line break or control character", .value.escape_debug())]
+    ControlCharacter { kind: &'static str, value: String },
+
+    #[error("{kind} '{value}' contains whitespace")]
+    Whitespace { kind: &'static str, value: String },
+
+    /// Errors in the rendered source, which is kept so they can be shown in place
+    #[error("the document is invalid")]
+    Invalid { text: String, errors: Vec<ValidationError> },
+
+    #[error("{0} does not read back as written")]
+    Mismatch(String),
+}
+
+/// Render a document built outside the parser, e.g. deserialized, as source text
+///
+/// Only what a writer states is used: names, claim text and inline elements.
+/// Spans are ignored, and brief forms and modifiers are left in the claim text
+/// to be parsed again. Marker characters in text are escaped, so they stay
+/// text. Pass the result to [`format_source`] to validate it and put it in
+/// canonical form, or use [`import_tree`] to do both and check the result.
+pub fn format_tree(document: &Document) -> String {
+    format_document(&escape_tree(document))
+}
+
+/// Render a document built outside the parser in canonical form, checking that
+/// the source reads back as the same tree
+///
+/// Names and inline elements that would spill onto another line or into
+/// another element are refused, as are documents with errors that
+/// [`format_source`] refuses.
+pub fn import_tree(document: &Document) -> Result<String, TreeError> {
+    check_values(document)?;
+    let text = format_tree(document);
+    let formatted = format_source(&text).map_err(|errors| TreeError::Invalid { text, errors })?;
+    match difference(&stated(&escape_tree(document)), &stated(&escape_tree(&parse(&formatted)))) {
+        Some((location, _)) => Err(TreeError::Mismatch(location)),
+        None => Ok(formatted),
+    }
+}
+
+/// The tree with marker characters in text escaped, and derived elements cleared
+fn escape_tree(document: &Document) -> Document {
+    let mut document = document.clone();
+    let claims = document.concepts.iter_mut().flat_map(|c| &mut c.facets).flat_map(|f| &mut f.claims);
+    for claim in claims {
+        let data = &mut claim.data;
+        // Unescape first, so text that is already escaped is left as it is
+        data.text = escape(&unescape(&data.text)).into_owned();
+        for condition in &mut data.conditions {
+            condition.value = escape(&unescape(condition)).into_owned();
+        }
+        if let Some(evolution) = &mut data.evolution {
+            evolution.prior_belief = escape(&unescape(&evolution.prior_belief)).into_owned();
+        }
+        data.brief_forms.clear();
+        data.modifiers.clear();
+    }
+    document
+}
+
+/// Refuse values that would break out of their line, or, for sources and
+/// references, out of their element
+fn check_values(document: &Document) -> Result<(), TreeError> {
+    let mut values: Vec<(&'static str, String)> = Vec::new();
+    values.extend(document.allow.iter().map(|lint| ("lint", lint.value.clone())));
+    for concept in &document.concepts {
+        values.push(("concept", concept.name.clone()));
+        for facet in &concept.facets {
+            values.push(("facet", facet.name.clone()));
+            for claim in &facet.claims {
+                let data = &claim.data;
+                values.push(("claim", data.text.clone()));
+                values.extend(data.conditions.iter().map(|c| ("condition", c.value.clone())));
+                values.extend(data.sources.iter().map(|s| ("source", s.value.clone())));
+                values.extend(data.references.iter().map(|r| ("reference", r.value.to_string())));
+                values.extend(data.evolution.iter().map(|e| ("prior belief", e.prior_belief.clone())));
+            }
+        }
+    }
+
+    for (kind, value) in values {
+        if value.chars().any(char::is_control) {
+            return Err(TreeError::ControlCharacter { kind, value });
+        }
+        if matches!(kind, "lint" | "source" | "reference") && value.chars().any(char::is_whitespace) {
+            return Err(TreeError::Whitespace { kind, value });
+        }
+    }
+    Ok(())
+}
+
+/// The tree with the spans inside claims cleared, and whitespace, which
+/// formatting normalizes, removed from names and text
+///
+/// Concept, facet and claim spans are kept to locate differences, and are not
+/// compared.
+fn stated(document: &Document) -> Document {
+    let squeeze = |text: &str| text.split_whitespace().collect::<String>();
+    let mut document = document.clone();
+    for lint in &mut document.allow {
+        lint.span = Default::default();
+    }
+    for concept in &mut document.concepts {
+        concept.name = squeeze(&concept.name);
+        for facet in &mut concept.facets {
+            facet.name = squeeze(&facet.name);
+            for claim in &mut facet.claims {
+                let data = &mut claim.data;
+                data.text = squeeze(&data.text);
+                data.text_span = Default::default();
+                for condition in &mut data.conditions {
+                    condition.value = squeeze(condition);
+                    condition.span = Default::default();
+                }
+                for source in &mut data.sources {
+                    source.span = Default::default();
+                }
+                for reference in &mut data.references {
+                    reference.span = Default::default();
+                }
+                for brief_form in &mut data.brief_forms {
+                    brief_form.span = Default::default();
+                }
+                for modifier in &mut data.modifiers {
+                    modifier.span = Default::default();
+                }
+                if let Some(evolution) = &mut data.evolution {
+                    evolution.prior_belief = squeeze(&evolution.prior_belief);
+                    evolution.span = Default::default();
+                }
+            }
+        }
+    }
+    document
+}
+
+/// Where `actual` first differs from `expected`: a description, and the span
+/// of the element in `expected`
+fn difference(expected: &Document, actual: &Document) -> Option<(String, Span)> {
+    let first = expected.concepts.first().map_or_else(Span::default, |c| c.span);
+    if expected.allow != actual.allow {
+        return Some(("the allow directive".to_string(), first));
+    }
+    if expected.concepts.len() != actual.concepts.len() {
+        return Some(("the list of concepts".to_string(), first));
+    }
+    for (concept, read) in expected.concepts.iter().zip(&actual.concepts) {
+        if concept.name != read.name || concept.facets.len() != read.facets.len() {
+            return Some((format!("concept '{}'", concept.name), concept.span));
+        }
+        for (facet, read) in concept.facets.iter().zip(&read.facets) {
+            if facet.name != read.name || facet.claims.len() != read.claims.len() {
+                return Some((format!("facet '{}.{}'", concept.name, facet.name), facet.span));
+            }
+            for (index, (claim, read)) in facet.claims.iter().zip(&read.claims).enumerate() {
+                if claim.data != read.data {
+                    let location = format!("claim {} of '{}.{}'", index + 1, concept.name, facet.name);
+                    return Some((location, claim.span));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Render a single claim line in canonical form, without indentation
+pub fn format_claim(claim: &ClaimData) -> String {
+    let mut line = String::from("- ");
+    line.push_str(&normalize_claim_text(claim));
+    for condition in &claim.conditions {
+        line.push_str(" | ");
+        line.push_str(&collapse_whitespace(condition));
+    }
+    for source in &claim.sources {
+        line.push_str(" @");
+        line.push_str(source);
+    }
+    for reference in &claim.references {
+        line.push_str(" &");
+        line.push_str(&reference.to_string());
+    }
+    if let Some(evolution) = &claim.evolution {
+        line.push_str(" [<= ");
+        line.push_str(&collapse_whitespace(&evolution.prior_belief));
+        line.push(']');
+    }
+    line
+}
+
+/// Claim text with single spaces around symbolic brief form operators
+///
+/// An operator glued to anything but a plain word, such as a modifier or
+/// another symbol, is left as written: spacing it out could change how it reads.
+fn normalize_claim_text(claim: &ClaimData) -> String {
+    let text = &claim.text;
+    let tokens = lex_claim(text, Span::default());
+    let plain_word = |token: &Token| {
+        token.kind == TokenKind::Word && token.text.chars().any(char::is_alphanumeric)
+    };
+
+    let mut spaced = String::with_capacity(text.len() + 8);
+    let mut last = 0;
+    for (idx, token) in tokens.iter().enumerate() {
+        // Word operators (`vs`) are already delimited by whitespace
+        if token.kind != TokenKind::Operator || token.text.chars().all(char::is_alphabetic) {
+            continue;
+        }
+        let (start, end) = (token.span.start, token.span.end);
+        let before = idx.checked_sub(1).map(|i| &tokens[i]).filter(|t| t.span.end == start);
+        let after = tokens.get(idx + 1).filter(|t| t.span.start == end);
+        if !before.is_none_or(plain_word) || !after.is_none_or(plain_word) {
+            continue;
+        }
+        spaced.push_str(&text[last..start]);
+        spaced.push(' ');
+        spaced.push_str(token.text);
+        spaced.push(' ');
+        last = end;
+    }
+    spaced.push_str(&text[last..]);
+
+    collapse_whitespace(&spaced)
+}
+
+/// Trim and replace every run of whitespace with a single space
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Reference, Span, Spanned};
+
+    #[test]
+    fn test_format_normalizes_spacing() {
+        let input = "Power  \n  .core\n    -corrupts   |  unchecked   @history  \n    - power=>corruption\n\n\n\nTrust\n  .formation\n    - slow  &Power.core\n    -   needs  time[<=  instant ]\n";
+
+        let formatted = format_source(input).unwrap();
+        assert_eq!(
+            formatted,
+            "Power\n  .core\n    - corrupts | unchecked @history\n    - power => corruption\n\nTrust\n  .formation\n    - slow &Power.core\n    - needs time [<= instant]\n"
+        );
+    }
+
+    #[test]
+    fn test_format_refuses_misplaced_elements() {
+        // Reordering is left to `worldview fix`, which can tell stray text from conditions
+        let errors = format_source("Power\n  .core\n    - corrupts @history | unchecked\n").unwrap_err();
+        assert!(matches!(errors[0], ValidationError::MisplacedClaimElement { .. }));
+    }
+
+    #[test]
+    fn test_format_merges_directives() {
+        let input = "\n#![allow( long-claim )]\n#![allow(missing-source,WVF0022)]\nPower\n  .core\n    - ^ corrupts\n";
+        let formatted = format_source(input).unwrap();
+        assert_eq!(
+            formatted,
+            "#![allow(long-claim, missing-source, WVF0022)]\n\nPower\n  .core\n    - ^ corrupts\n"
+        );
+        assert_eq!(format_source(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_format_is_idempotent() {
+        let input = "Trust\n  .erosion\n    - fast   !\n    - single violation=>collapse?\n    -  asymmetric vs formation &Trust.erosion\nPower\n  .core\n    - a~b | x |y\n";
+
+        let once = format_source(input).unwrap();
+        let twice = format_source(&once).unwrap();
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn test_format_keeps_glued_operators() {
+        // Spacing these operators out would change how the claim reads
+        for claim in ["a =>~ b", "\\|=>~", "growth=>! decline"] {
+            let input = format!("Power\n  .core\n    - {}\n", claim);
+            let once = format_source(&input).unwrap();
+            assert_eq!(once, input);
+            assert!(validate(&once).errors.is_empty(), "{}", once);
+            assert_eq!(format_source(&once).unwrap(), once);
+        }
+    }
+
+    #[test]
+    fn test_difference_locates_changed_claim() {
+        let document = parse("Growth\n  .outlook\n    - steady\n    - cost =>~ price\n");
+        let respaced = parse("Growth\n  .outlook\n    - steady\n    - cost => ~ price\n");
+        let claim = &document.concepts[0].facets[0].claims[1];
+        assert_eq!(
+            difference(&stated(&document), &stated(&respaced)),
+            Some(("claim 2 of 'Growth.outlook'".to_string(), claim.span))
+        );
+    }
+
+    #[test]
+    fn test_format_keeps_claim_order() {
+        let input = "Trust\n  .formation\n    - slow\n    - fast\n    - contextual\n";
+        let formatted = parse(&format_source(input).unwrap());
+        let claims: Vec<_> = formatted.claims().map(|(_, _, c)| c.data.text.clone()).collect();
+        assert_eq!(claims, ["slow", "fast", "contextual"]);
+    }
+
+    #[test]
+    fn test_format_keeps_escapes() {
+        let input = "Market\n  .telecom\n    - AT\\&T  dominates |  1980s \\[<= x\\]\n";
+        let formatted = format_source(input).unwrap();
+        assert_eq!(formatted, "Market\n  .telecom\n    - AT\\&T dominates | 1980s \\[<= x\\]\n");
+    }
+
+    #[test]
+    fn test_example_is_canonical() {
+        let example = include_str!("../../example.wvf");
+        assert_eq!(format_source(example).unwrap(), example);
+    }
+
+    #[test]
+    fn test_format_tree_ignores_stale_spans() {
+        // A parsed tree edited afterwards: the brief form's span no longer locates its operator
+        let mut document = parse("Power\n  .core\n    - concentration=>abuse | unchecked &Power\n");
+        document.concepts[0].facets[0].claims[0].data.text = "abuse=>concentration".to_string();
+
+        let formatted = format_source(&format_tree(&document)).unwrap();
+        assert_eq!(formatted, "Power\n  .core\n    - abuse => concentration | unchecked &Power\n");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_format_tree_from_json() {
+        let json = r#"{"concepts": [{"name": "Trust", "facets": [{"name": "formation", "claims": [
+            {"data": {"text": "slow", "sources": [{"value": "experience"}]}},
+            {"data": {"text": "needs time", "references": [{"value": {"concept": "Power", "facet": "core"}}],
+                      "evolution": {"prior_belief": "instant"}}}
+        ]}]}, {"name": "Power", "facets": [{"name": "core", "claims": [{"data": {"text": "corrupts"}}]}]}]}"#;
+        let document: Document = serde_json::from_str(json).unwrap();
+
+        let formatted = format_source(&format_tree(&document)).unwrap();
+        assert_eq!(
+            formatted,
+            "Trust\n  .formation\n    - slow @experience\n    - needs time &Power.core [<= instant]\n\nPower\n  .core\n    - corrupts\n"
+        );
+    }
+
+    #[test]
+    fn test_import_tree_escapes_markers() {
+        let mut document = parse("Market\n  .telecom\n    - AT\\&T dominates\n    - placeholder\n");
+        let claim = &mut document.concepts[0].facets[0].claims[1].data;
+        claim.text = "ops | support @ corp".to_string();
+        claim.conditions.push(Spanned { value: "[<= never]".to_string(), span: Span::default() });
+
+        assert_eq!(
+            import_tree(&document).unwrap(),
+            "Market\n  .telecom\n    - AT\\&T dominates\n    - ops \\| support \\@ corp | \\[<= never\\]\n"
+        );
+    }
+
+    #[test]
+    fn test_import_tree_refuses_injection() {
+        let document = parse("Power\n  .core\n    - corrupts\n");
+        let edited = |edit: fn(&mut Document)| {
+            let mut document = document.clone();
+            edit(&mut document);
+            import_tree(&document)
+        };
+
+        let result = edited(|d| d.concepts[0].name = "Power\n  .extra".to_string());
+        assert!(matches!(result, Err(TreeError::ControlCharacter { kind: "concept", .. })));
+        let result = edited(|d| {
+            let source = Spanned { value: "history\nTrust".to_string(), span: Span::default() };
+            d.concepts[0].facets[0].claims[0].data.sources.push(source);
+        });
+        assert!(matches!(result, Err(TreeError::ControlCharacter { kind: "source", .. })));
+        let result = edited(|d| {
+            let source = Spanned { value: "history | unchecked".to_string(), span: Span::default() };
+            d.concepts[0].facets[0].claims[0].data.sources.push(source);
+        });
+        assert!(matches!(result, Err(TreeError::Whitespace { kind: "source", .. })));
+
+        // A reference to a concept named `Power.core` reads back as one to a facet
+        let result = edited(|d| {
+            let reference = Reference { concept: "Power.core".to_string(), facet: None };
+            d.concepts[0].facets[0].claims[0].data.references.push(Spanned { value: reference, span: Span::default() });
+        });
+        assert_eq!(result, Err(TreeError::Mismatch("claim 1 of 'Power.core'".to_string())));
+
+        let result = edited(|d| d.concepts[0].name = " ".to_string());
+        assert!(matches!(result, Err(TreeError::Invalid { errors, .. }) if matches!(errors[0], ValidationError::OrphanFacet { .. })));
+    }
+
+    #[test]
+    fn test_format_allows_errors_that_keep_text() {
+        // The reference may be defined in another workspace file
+        let input = "Power\n  .core\n    - corrupts  &Trust.formation\n    - corrupts\n";
+        assert_eq!(
+            format_source(input).unwrap(),
+            "Power\n  .core\n    - corrupts &Trust.formation\n    - corrupts\n"
+        );
+    }
+
+    #[test]
+    fn test_format_refuses_invalid_documents() {
+        let errors = format_source("Power\n  .core\n   - corrupts\n").unwrap_err();
+        assert!(matches!(errors[0], ValidationError::InvalidIndentation { .. }));
+    }
+}
@@ -0,0 +1,470 @@
+//! Tokenizer for Worldview documents
+//!
+//! [`lex`] splits a document into typed tokens with spans, for syntax
+//! highlighters and other tools that work below the level of the tree.
+//! Claim parsing is built on the same tokens.
+//!
+//! Brief form operators and modifiers are recognised on token boundaries:
+//! `vs` is an operator only as a whole word (never inside `canvas`), symbolic
+//! operators split the text around them (`power=>corruption`), and modifiers
+//! are a trailing run of `^ ! ? *` after a word, or a standalone symbol.
+//!
+//! A backslash before a marker character (`\&`, `\|`, `\[`) makes it literal
+//! text. Escapes are kept in token text; use [`unescape`] to resolve them.
+
+use crate::{BRIEF_FORMS, ESCAPABLE, ESCAPE, MODIFIERS, Span, count_leading_spaces, trim_range};
+use std::borrow::Cow;
+
+/// The kind of a [`Token`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// A concept name (an unindented line)
+    Concept,
+    /// The `.` before a facet name
+    FacetPrefix,
+    /// A facet name
+    Facet,
+    /// The `-` before a claim
+    ClaimPrefix,
+    /// Ordinary text in a claim, condition or prior belief
+    Word,
+    /// A brief form operator such as `=>` or `vs`
+    Operator,
+    /// A modifier such as `^` or `?`
+    Modifier,
+    /// The `|` before a condition
+    ConditionMarker,
+    /// The `@` before a source
+    SourceMarker,
+    /// A source name
+    Source,
+    /// The `&` before a reference
+    RefMarker,
+    /// A reference target, `Concept` or `Concept.facet`
+    Reference,
+    /// The `[<=` opening an evolution marker
+    EvolutionOpen,
+    /// The `]` closing an evolution marker
+    EvolutionClose,
+}
+
+/// A token and its location in the document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub span: Span,
+}
+
+/// Split a document into tokens, in source order
+///
+/// Lexing never fails: lines that would not validate are still tokenized as
+/// well as possible, so highlighters can colour documents being edited.
+pub fn lex(input: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut offset = 0;
+
+    for (idx, chunk) in input.split_inclusive('\n').enumerate() {
+        let line = chunk.strip_suffix('\n').unwrap_or(chunk);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let line_span = Span {
+            line: idx + 1,
+            column: 1,
+            start: offset,
+            end: offset + line.len(),
+        };
+        offset += chunk.len();
+
+        let content = trim_range(line, 0..line.len());
+        if content.is_empty() {
+            continue;
+        }
+        let token = |kind, range: std::ops::Range<usize>| Token {
+            kind,
+            text: &line[range.clone()],
+            span: line_span.subspan(line, range),
+        };
+
+        if count_leading_spaces(line) == 0 {
+            tokens.push(token(TokenKind::Concept, content));
+        } else if line[content.clone()].starts_with('.') {
+            tokens.push(token(TokenKind::FacetPrefix, content.start..content.start + 1));
+            let name = trim_range(line, content.start + 1..content.end);
+            if !name.is_empty() {
+                tokens.push(token(TokenKind::Facet, name));
+            }
+        } else if line[content.clone()].starts_with('-') {
+            tokens.push(token(TokenKind::ClaimPrefix, content.start..content.start + 1));
+            let body = content.start + 1..content.end;
+            let body_span = line_span.subspan(line, body.clone());
+            tokens.extend(lex_claim(&line[body], body_span));
+        } else {
+            // A line with a missing prefix: lex its content like a claim body
+            let body_span = line_span.subspan(line, content.clone());
+            tokens.extend(lex_claim(&line[content], body_span));
+        }
+    }
+
+    tokens
+}
+
+/// Split claim content into tokens
+///
+/// `span` is the location of `text` in the document.
+pub(crate) fn lex_claim(text: &str, span: Span) -> Vec<Token<'_>> {
+    let mut lexer = ClaimLexer {
+        text,
+        span,
+        tokens: Vec::new(),
+        in_evolution: false,
+    };
+    lexer.run();
+    lexer.tokens
+}
+
+struct ClaimLexer<'a> {
+    text: &'a str,
+    span: Span,
+    tokens: Vec<Token<'a>>,
+    /// Inside `[<= ...`, where `]` closes the marker
+    in_evolution: bool,
+}
+
+impl<'a> ClaimLexer<'a> {
+    fn run(&mut self) {
+        let mut pos = 0;
+        while let Some(c) = self.text[pos..].chars().next() {
+            let rest = &self.text[pos..];
+            pos = if c.is_whitespace() {
+                pos + c.len_utf8()
+            } else if c == '|' {
+                self.push(TokenKind::ConditionMarker, pos..pos + 1)
+            } else if c == '@' || c == '&' {
+                let (marker, name) = if c == '@' {
+                    (TokenKind::SourceMarker, TokenKind::Source)
+                } else {
+                    (TokenKind::RefMarker, TokenKind::Reference)
+                };
+                self.push(marker, pos..pos + 1);
+                let end = self.name_end(pos + 1);
+                if end > pos + 1 {
+                    self.push(name, pos + 1..end);
+                }
+                end
+            } else if rest.starts_with("[<=") {
+                self.in_evolution = true;
+                self.push(TokenKind::EvolutionOpen, pos..pos + 3)
+            } else if c == ']' && self.in_evolution {
+                self.in_evolution = false;
+                self.push(TokenKind::EvolutionClose, pos..pos + 1)
+            } else {
+                let end = self.run_end(pos);
+                self.lex_run(pos, end);
+                end
+            };
+        }
+    }
+
+    /// Record a token covering `range`, returning the offset after it
+    fn push(&mut self, kind: TokenKind, range: std::ops::Range<usize>) -> usize {
+        let end = range.end;
+        self.tokens.push(Token {
+            kind,
+            text: &self.text[range.clone()],
+            span: self.span.subspan(self.text, range),
+        });
+        end
+    }
+
+    /// End of a source or reference name starting at `start`
+    fn name_end(&self, start: usize) -> usize {
+        self.text[start..]
+            .find(|c: char| c.is_whitespace() || matches!(c, '|' | '@' | '&') || (c == ']' && self.in_evolution))
+            .map_or(self.text.len(), |i| start + i)
+    }
+
+    /// End of a run of text starting at `start`: whitespace, a marker, or `[<=` ends it
+    ///
+    /// Escaped characters never end a run.
+    fn run_end(&self, start: usize) -> usize {
+        let mut chars = self.text[start..].char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if c == ESCAPE && chars.next_if(|&(_, next)| ESCAPABLE.contains(&next)).is_some() {
+                continue;
+            }
+            if c.is_whitespace()
+                || matches!(c, '|' | '@' | '&')
+                || (c == ']' && self.in_evolution)
+                || (i > 0 && self.text[start + i..].starts_with("[<="))
+            {
+                return start + i;
+            }
+        }
+        self.text.len()
+    }
+
+    /// Lex a whitespace-free run into words, operators and modifiers
+    fn lex_run(&mut self, start: usize, end: usize) {
+        let run = &self.text[start..end];
+
+        // Word operators (`vs`) only count as a whole run
+        if word_operators().any(|op| op == run) {
+            self.push(TokenKind::Operator, start..end);
+            return;
+        }
+        // `v` is a modifier only when it follows a term
+        if word_modifiers().any(|m| m == run) {
+            let kind = match self.tokens.last() {
+                Some(prev) if prev.kind == TokenKind::Word => TokenKind::Modifier,
+                _ => TokenKind::Word,
+            };
+            self.push(kind, start..end);
+            return;
+        }
+
+        // Symbolic operators split the run into pieces
+        let mut piece_start = start;
+        let mut i = start;
+        while i < end {
+            if let Some(op) = self.operator_at(i, end) {
+                self.lex_piece(piece_start, i);
+                self.push(TokenKind::Operator, i..i + op.len());
+                i += op.len();
+                piece_start = i;
+            } else {
+                i += self.text[i..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        self.lex_piece(piece_start, end);
+    }
+
+    /// The symbolic operator starting at `pos`, if any
+    ///
+    /// Operators that are part of a comparison such as `<=`, `>=`, `==` or `!=`
+    /// are not brief forms.
+    fn operator_at(&self, pos: usize, end: usize) -> Option<&'static str> {
+        let rest = &self.text[pos..end];
+        let op = symbolic_operators().find(|op| rest.starts_with(*op))?;
+        let prev = self.text[..pos].chars().next_back();
+        let next = self.text[pos + op.len()..end].chars().next();
+        if prev.is_some_and(|c| matches!(c, '<' | '>' | '=' | '!')) || next == Some('=') {
+            return None;
+        }
+        Some(op)
+    }
+
+    /// Lex an operator-free piece: a word, then any trailing modifiers
+    fn lex_piece(&mut self, start: usize, end: usize) {
+        let piece = &self.text[start..end];
+        if piece.is_empty() {
+            return;
+        }
+        let stem = piece.trim_end_matches(|c| symbol_modifiers().any(|m| m == c));
+        // A stem without letters or digits (e.g. `?!`) is not a term
+        if stem.chars().any(char::is_alphanumeric) {
+            self.push(TokenKind::Word, start..start + stem.len());
+        } else if !stem.is_empty() {
+            self.push(TokenKind::Word, start..end);
+            return;
+        }
+        for (i, c) in piece[stem.len()..].char_indices() {
+            let at = start + stem.len() + i;
+            self.push(TokenKind::Modifier, at..at + c.len_utf8());
+        }
+    }
+}
+
+/// Resolve escape sequences, so `AT\&T` reads `AT&T`
+///
+/// A backslash before any other character is kept as is.
+pub fn unescape(text: &str) -> Cow<'_, str> {
+    if !text.contains(ESCAPE) {
+        return Cow::Borrowed(text);
+    }
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.next_if(|next| c == ESCAPE && ESCAPABLE.contains(next)) {
+            Some(escaped) => unescaped.push(escaped),
+            None => unescaped.push(c),
+        }
+    }
+    Cow::Owned(unescaped)
+}
+
+/// Escape every marker character, so `AT&T` stays literal text as `AT\&T`
+pub fn escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(ESCAPABLE) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 4);
+    for c in text.chars() {
+        if ESCAPABLE.contains(&c) {
+            escaped.push(ESCAPE);
+        }
+        escaped.push(c);
+    }
+    Cow::Owned(escaped)
+}
+
+/// Alphabetic brief form operators, e.g. `vs`
+fn word_operators() -> impl Iterator<Item = &'static str> {
+    BRIEF_FORMS
+        .iter()
+        .map(|(op, _)| *op)
+        .filter(|op| op.chars().all(char::is_alphabetic))
+}
+
+/// Symbolic brief form operators, longest first so `=>` wins over `=`
+fn symbolic_operators() -> impl Iterator<Item = &'static str> {
+    let mut ops: Vec<&'static str> = BRIEF_FORMS
+        .iter()
+        .map(|(op, _)| *op)
+        .filter(|op| !op.chars().all(char::is_alphabetic))
+        .collect();
+    ops.sort_by_key(|op| std::cmp::Reverse(op.len()));
+    ops.into_iter()
+}
+
+/// Alphabetic modifiers, e.g. `v`
+fn word_modifiers() -> impl Iterator<Item = &'static str> {
+    MODIFIERS
+        .iter()
+        .map(|(m, _)| *m)
+        .filter(|m| m.chars().all(char::is_alphabetic))
+}
+
+/// Symbolic modifier characters, e.g. `^` and `?`
+fn symbol_modifiers() -> impl Iterator<Item = char> {
+    MODIFIERS
+        .iter()
+        .filter_map(|(m, _)| m.chars().next())
+        .filter(|c| !c.is_alphabetic())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TokenKind::*;
+
+    fn kinds(input: &str) -> Vec<(TokenKind, &str)> {
+        lex(input).into_iter().map(|t| (t.kind, t.text)).collect()
+    }
+
+    #[test]
+    fn test_lex_document() {
+        let input = "Power\n  .core\n    - concentration^ => abuse | unchecked @history &Trust.formation\n";
+        assert_eq!(
+            kinds(input),
+            [
+                (Concept, "Power"),
+                (FacetPrefix, "."),
+                (Facet, "core"),
+                (ClaimPrefix, "-"),
+                (Word, "concentration"),
+                (Modifier, "^"),
+                (Operator, "=>"),
+                (Word, "abuse"),
+                (ConditionMarker, "|"),
+                (Word, "unchecked"),
+                (SourceMarker, "@"),
+                (Source, "history"),
+                (RefMarker, "&"),
+                (Reference, "Trust.formation"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_token_spans() {
+        let input = "Power\n  .core\n    - a~b\n";
+        let tokens = lex(input);
+        for token in &tokens {
+            assert_eq!(&input[token.span.start..token.span.end], token.text);
+        }
+        let tilde = tokens.iter().find(|t| t.kind == Operator).unwrap();
+        assert_eq!((tilde.span.line, tilde.span.column), (3, 8));
+    }
+
+    #[test]
+    fn test_word_operators_need_boundaries() {
+        assert_eq!(kinds("    - canvas obvious versus"), [(ClaimPrefix, "-"), (Word, "canvas"), (Word, "obvious"), (Word, "versus")]);
+        assert_eq!(kinds("    - a vs b")[2], (Operator, "vs"));
+    }
+
+    #[test]
+    fn test_symbolic_operators_split_words() {
+        assert_eq!(
+            kinds("    - power=>corruption x<=y"),
+            [
+                (ClaimPrefix, "-"),
+                (Word, "power"),
+                (Operator, "=>"),
+                (Word, "corruption"),
+                (Word, "x<=y"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modifiers() {
+        assert_eq!(
+            kinds("    - fast!? ?! growth v v"),
+            [
+                (ClaimPrefix, "-"),
+                (Word, "fast"),
+                (Modifier, "!"),
+                (Modifier, "?"),
+                (Modifier, "?"),
+                (Modifier, "!"),
+                (Word, "growth"),
+                (Modifier, "v"),
+                (Word, "v"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_evolution_marker() {
+        assert_eq!(
+            kinds("    - adaptive [<= good] x]"),
+            [
+                (ClaimPrefix, "-"),
+                (Word, "adaptive"),
+                (EvolutionOpen, "[<="),
+                (Word, "good"),
+                (EvolutionClose, "]"),
+                (Word, "x]"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_escapes_are_text() {
+        assert_eq!(
+            kinds(r"    - AT\&T a\|b \[<= x\] | y"),
+            [
+                (ClaimPrefix, "-"),
+                (Word, r"AT\&T"),
+                (Word, r"a\|b"),
+                (Word, r"\[<="),
+                (Word, r"x\]"),
+                (ConditionMarker, "|"),
+                (Word, "y"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape(r"AT\&T \| \\ \d"), r"AT&T | \ \d");
+        assert!(matches!(unescape("plain"), Cow::Borrowed("plain")));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape(r"AT&T | [<= x] \d"), r"AT\&T \| \[<= x\] \\d");
+        assert_eq!(unescape(&escape(r"a|b \| c")), r"a|b \| c");
+        assert!(matches!(escape("plain"), Cow::Borrowed("plain")));
+    }
+}
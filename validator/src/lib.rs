@@ -3,102 +3,521 @@
 //! This crate provides validation for `.wvf` files according to the Worldview specification.
 //! It checks structural correctness (hierarchy, indentation), claim syntax, brief forms,
 //! modifiers, and evolution markers.
+//!
+//! Use [`parse`] to get the Concept → Facet → Claim tree of a document, or
+//! [`validate`] to get the same tree together with any diagnostics. Every
+//! diagnostic and parsed element carries a [`Span`] locating it in the source.
+//!
+//! The checks are [`rule::Rule`]s; a [`Validator`] runs your own rules
+//! alongside the built-in ones. With the `rhai` feature, `script::ScriptRule`
+//! runs a rule written as a Rhai script.
+//!
+//! With the `pest` feature, `strict::validate_strict` also checks documents
+//! against the PEG grammar in `spec/grammar.pest`.
+//!
+//! With the `serde` feature, the parsed model can be serialized and
+//! deserialized, and a [`ValidationResult`] serialized with its diagnostics.
+//! `spec/parse.schema.json` describes the JSON form.
 
+use std::borrow::Cow;
 use std::collections::HashSet;
 use std::fmt;
+use std::ops::{Deref, Range, RangeInclusive};
+use lexer::{Token, TokenKind};
+use lint::{Level, LintConfig, LintError};
+use rule::{Rule, RuleContext};
 use thiserror::Error;
 
+pub mod codes;
+pub mod conformance;
+pub mod expand;
+pub mod fix;
+pub mod format;
+pub mod lexer;
+pub mod lint;
+pub mod markdown;
+pub mod render;
+pub mod rule;
+#[cfg(feature = "rhai")]
+pub mod script;
+#[cfg(feature = "pest")]
+pub mod strict;
+pub mod workspace;
+
 // Token definitions generated at compile time from spec/tokens.yaml
 include!(concat!(env!("OUT_DIR"), "/tokens.rs"));
 
+/// A location in the source document
+///
+/// Spans never cross a line boundary. `start` and `end` are byte offsets into
+/// the whole document, so `&source[span.start..span.end]` is the spanned text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column (in characters) of the first spanned character
+    pub column: usize,
+    /// Byte offset of the first spanned character
+    pub start: usize,
+    /// Byte offset one past the last spanned character
+    pub end: usize,
+}
+
+impl Span {
+    /// Narrow this span to `range`, a byte range within `text`, where `text`
+    /// is the source text this span covers (or starts with)
+    pub fn subspan(&self, text: &str, range: Range<usize>) -> Span {
+        Span {
+            line: self.line,
+            column: self.column + text[..range.start].chars().count(),
+            start: self.start + range.start,
+            end: self.start + range.end,
+        }
+    }
+
+    /// Length of the span in bytes
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Returns true if the span covers no text (e.g. a missing element)
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// A parsed value together with its location in the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Spanned<T> {
+    pub value: T,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub span: Span,
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    /// A stylistic suggestion
+    Hint,
+    /// Worth knowing, no action needed
+    Info,
+    /// Probably unintended, but the document is still valid
+    Warning,
+    /// The document is invalid
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::Hint => "hint",
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        f.pad(name)
+    }
+}
+
 /// Errors that can occur during Worldview validation
 #[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum ValidationError {
     // Structural errors
-    #[error("line {line}: invalid indentation (expected {expected} spaces, found {found})")]
+    #[error("line {}: invalid indentation (expected {expected} spaces, found {found})", .span.line)]
     InvalidIndentation {
-        line: usize,
+        span: Span,
         expected: &'static str,
         found: usize,
     },
 
-    #[error("line {line}: facet must have '.' prefix")]
-    MissingFacetPrefix { line: usize },
+    #[error("line {}: facet must have '.' prefix", .span.line)]
+    MissingFacetPrefix { span: Span },
 
-    #[error("line {line}: claim must have '-' prefix")]
-    MissingClaimPrefix { line: usize },
+    #[error("line {}: claim must have '-' prefix", .span.line)]
+    MissingClaimPrefix { span: Span },
 
-    #[error("line {line}: concept '{concept}' has no facets")]
-    ConceptWithoutFacets { line: usize, concept: String },
+    #[error("line {}: concept '{concept}' has no facets", .span.line)]
+    ConceptWithoutFacets {
+        span: Span,
+        concept: String,
+        /// Where the next concept starts, if any
+        next: Option<Span>,
+    },
 
-    #[error("line {line}: facet '{facet}' has no claims")]
-    FacetWithoutClaims { line: usize, facet: String },
+    #[error("line {}: facet '{facet}' has no claims", .span.line)]
+    FacetWithoutClaims {
+        span: Span,
+        facet: String,
+        /// Where the next facet or concept starts, if any
+        next: Option<Span>,
+    },
 
-    #[error("line {line}: orphan facet (no preceding concept)")]
-    OrphanFacet { line: usize },
+    #[error("line {}: orphan facet (no preceding concept)", .span.line)]
+    OrphanFacet { span: Span },
 
-    #[error("line {line}: orphan claim (no preceding facet)")]
-    OrphanClaim { line: usize },
+    #[error("line {}: orphan claim (no preceding facet)", .span.line)]
+    OrphanClaim { span: Span },
 
-    #[error("line {line}: empty claim text")]
-    EmptyClaimText { line: usize },
+    #[error("line {}: empty claim text", .span.line)]
+    EmptyClaimText { span: Span },
 
-    #[error("line {line}: unexpected indentation level ({found} spaces)")]
-    UnexpectedIndentation { line: usize, found: usize },
+    #[error("line {}: unexpected indentation level ({found} spaces)", .span.line)]
+    UnexpectedIndentation { span: Span, found: usize },
 
-    #[error("line {line}: concept name cannot be empty")]
-    EmptyConceptName { line: usize },
+    #[error("line {}: concept name cannot be empty", .span.line)]
+    EmptyConceptName { span: Span },
 
-    #[error("line {line}: facet name cannot be empty")]
-    EmptyFacetName { line: usize },
+    #[error("line {}: facet name cannot be empty", .span.line)]
+    EmptyFacetName { span: Span },
 
     // Inline element errors
-    #[error("line {line}: invalid reference format '{reference}' (expected &Concept.facet)")]
-    InvalidReferenceFormat { line: usize, reference: String },
+    #[error("line {}: invalid reference format '{reference}' (expected &Concept or &Concept.facet)", .span.line)]
+    InvalidReferenceFormat { span: Span, reference: String },
+
+    #[error("line {}: undefined reference '{reference}' (no such {} in document{})", .span.line, target_kind(.reference), did_you_mean(.suggestions))]
+    UndefinedReference {
+        span: Span,
+        reference: String,
+        /// Defined targets with a similar name, closest first
+        suggestions: Vec<String>,
+    },
+
+    #[error("line {}: empty condition (standalone '|')", .span.line)]
+    EmptyCondition { span: Span },
+
+    #[error("line {}: empty source (standalone '@')", .span.line)]
+    EmptySource { span: Span },
 
-    #[error("line {line}: undefined reference '{reference}' (no such concept.facet in document)")]
-    UndefinedReference { line: usize, reference: String },
+    #[error("line {}: empty reference (standalone '&')", .span.line)]
+    EmptyReference { span: Span },
 
-    #[error("line {line}: empty condition (standalone '|')")]
-    EmptyCondition { line: usize },
+    #[error("line {}: '{marker}' does not start {}; did you mean to escape it?", .span.line, marker_role(*.marker))]
+    UnescapedMarker { span: Span, marker: char },
 
-    #[error("line {line}: empty source (standalone '@')")]
-    EmptySource { line: usize },
+    #[error("line {}: {} must come before {}", .span.line, element_name(.element), element_name(.after))]
+    MisplacedClaimElement {
+        span: Span,
+        /// The misplaced element, as named in `claim_order`
+        element: &'static str,
+        /// The element it follows but should precede
+        after: &'static str,
+        /// The whole claim, which the fix rewrites
+        claim: Span,
+    },
+
+    // Name errors
+    #[error("line {}: concept '{concept}' cannot be referenced (names may contain only letters, digits, '-' and '_')", .span.line)]
+    UnreferenceableConcept { span: Span, concept: String },
 
-    #[error("line {line}: empty reference (standalone '&')")]
-    EmptyReference { line: usize },
+    #[error("line {}: invalid {kind} name '{name}' (names may contain only letters, digits, '-' and '_')", .span.line)]
+    InvalidIdentifier {
+        span: Span,
+        /// What is being named: `facet` or `source`
+        kind: &'static str,
+        name: String,
+    },
+
+    // Grammar errors (only reported by `strict::validate_strict`)
+    #[error("line {}: does not match the spec grammar ({expected})", .span.line)]
+    GrammarMismatch {
+        span: Span,
+        /// What the grammar expected at this point
+        expected: String,
+    },
 
     // Brief form errors
-    #[error("line {line}: brief form '{operator}' missing left operand")]
-    BriefFormMissingLeftOperand { line: usize, operator: String },
+    #[error("line {}: brief form '{operator}' missing left operand", .span.line)]
+    BriefFormMissingLeftOperand { span: Span, operator: String },
 
-    #[error("line {line}: brief form '{operator}' missing right operand")]
-    BriefFormMissingRightOperand { line: usize, operator: String },
+    #[error("line {}: brief form '{operator}' missing right operand", .span.line)]
+    BriefFormMissingRightOperand { span: Span, operator: String },
 
     // Evolution marker errors
-    #[error("line {line}: unclosed evolution marker '[<=' (missing ']')")]
-    UnclosedEvolutionMarker { line: usize },
+    #[error("line {}: unclosed evolution marker '[<=' (missing ']')", .span.line)]
+    UnclosedEvolutionMarker { span: Span },
 
-    #[error("line {line}: empty evolution marker '[<= ]' (no prior belief specified)")]
-    EmptyEvolutionMarker { line: usize },
+    #[error("line {}: empty evolution marker '[<= ]' (no prior belief specified)", .span.line)]
+    EmptyEvolutionMarker { span: Span },
 
-    #[error("line {line}: malformed evolution marker (expected '[<= prior belief]')")]
-    MalformedEvolutionMarker { line: usize },
+    #[error("line {}: malformed evolution marker (expected '[<= prior belief]')", .span.line)]
+    MalformedEvolutionMarker { span: Span },
 
     // Modifier warnings (these are softer - might be intentional)
-    #[error("line {line}: standalone modifier '{modifier}' may be unintentional")]
-    StandaloneModifier { line: usize, modifier: String },
+    #[error("line {}: standalone modifier '{modifier}' may be unintentional", .span.line)]
+    StandaloneModifier { span: Span, modifier: String },
+
+    // Duplicate declarations
+    #[error("line {}: concept '{concept}' is declared more than once", .span.line)]
+    DuplicateConcept {
+        span: Span,
+        concept: String,
+        /// Where the concept is first declared
+        first: Span,
+    },
+
+    #[error("line {}: facet '{facet}' is declared more than once in this concept", .span.line)]
+    DuplicateFacet {
+        span: Span,
+        facet: String,
+        /// Where the facet is first declared
+        first: Span,
+    },
+
+    #[error("line {}: claim repeats an earlier claim in this facet", .span.line)]
+    DuplicateClaim {
+        span: Span,
+        /// Where the claim is first stated
+        first: Span,
+    },
+
+    #[error("line {}: concept '{concept}' is already declared in {first_file} (line {})", .span.line, .first.line)]
+    CrossFileDuplicateConcept {
+        span: Span,
+        concept: String,
+        /// The workspace file that declares the concept first
+        first_file: String,
+        /// Where the concept is first declared, in `first_file`
+        first: Span,
+    },
+
+    // Lints (allowed unless configured, see `lint`)
+    #[error("line {}: claim text has {words} words (more than {max})", .span.line)]
+    LongClaim { span: Span, words: usize, max: usize },
+
+    #[error("line {}: claim cites no source", .span.line)]
+    MissingSource { span: Span },
+
+    // Directive errors
+    #[error("line {}: {reason}", .span.line)]
+    InvalidDirective {
+        span: Span,
+        /// What is wrong with the directive
+        reason: &'static str,
+    },
+
+    #[error("line {}: unknown lint '{lint}' in allow directive", .span.line)]
+    UnknownLint { span: Span, lint: String },
+
+    // Formatting errors (only reported by `format::format_source`)
+    #[error("line {}: formatting this line would change how it reads", .span.line)]
+    UnstableFormat { span: Span },
+
+    // Reported when a rule defined outside this crate cannot run (see `script`)
+    #[error("line {}: rule '{rule}' failed: {message}", .span.line)]
+    ScriptRuleFailed { span: Span, rule: String, message: String },
+
+    // Reported by rules defined outside this crate (see `rule`)
+    #[error("line {}: {message}", .span.line)]
+    Custom { span: Span, code: String, message: String },
 }
 
 impl ValidationError {
+    /// The stable diagnostic code, e.g. `WVF0013` (see [`codes`])
+    pub fn code(&self) -> &str {
+        match self {
+            ValidationError::InvalidIndentation { .. } => "WVF0001",
+            ValidationError::MissingFacetPrefix { .. } => "WVF0002",
+            ValidationError::MissingClaimPrefix { .. } => "WVF0003",
+            ValidationError::ConceptWithoutFacets { .. } => "WVF0004",
+            ValidationError::FacetWithoutClaims { .. } => "WVF0005",
+            ValidationError::OrphanFacet { .. } => "WVF0006",
+            ValidationError::OrphanClaim { .. } => "WVF0007",
+            ValidationError::EmptyClaimText { .. } => "WVF0008",
+            ValidationError::UnexpectedIndentation { .. } => "WVF0009",
+            ValidationError::EmptyConceptName { .. } => "WVF0010",
+            ValidationError::EmptyFacetName { .. } => "WVF0011",
+            ValidationError::InvalidReferenceFormat { .. } => "WVF0012",
+            ValidationError::UndefinedReference { .. } => "WVF0013",
+            ValidationError::EmptyCondition { .. } => "WVF0014",
+            ValidationError::EmptySource { .. } => "WVF0015",
+            ValidationError::EmptyReference { .. } => "WVF0016",
+            ValidationError::BriefFormMissingLeftOperand { .. } => "WVF0017",
+            ValidationError::BriefFormMissingRightOperand { .. } => "WVF0018",
+            ValidationError::UnclosedEvolutionMarker { .. } => "WVF0019",
+            ValidationError::EmptyEvolutionMarker { .. } => "WVF0020",
+            ValidationError::MalformedEvolutionMarker { .. } => "WVF0021",
+            ValidationError::StandaloneModifier { .. } => "WVF0022",
+            ValidationError::DuplicateConcept { .. } => "WVF0023",
+            ValidationError::DuplicateFacet { .. } => "WVF0024",
+            ValidationError::DuplicateClaim { .. } => "WVF0025",
+            ValidationError::UnescapedMarker { .. } => "WVF0026",
+            ValidationError::MisplacedClaimElement { .. } => "WVF0027",
+            ValidationError::UnreferenceableConcept { .. } => "WVF0028",
+            ValidationError::InvalidIdentifier { .. } => "WVF0029",
+            ValidationError::GrammarMismatch { .. } => "WVF0030",
+            ValidationError::CrossFileDuplicateConcept { .. } => "WVF0031",
+            ValidationError::LongClaim { .. } => "WVF0032",
+            ValidationError::MissingSource { .. } => "WVF0033",
+            ValidationError::InvalidDirective { .. } => "WVF0034",
+            ValidationError::UnknownLint { .. } => "WVF0035",
+            ValidationError::ScriptRuleFailed { .. } => "WVF0036",
+            ValidationError::UnstableFormat { .. } => "WVF0037",
+            ValidationError::Custom { code, .. } => code,
+        }
+    }
+
+    /// The severity of this diagnostic
+    pub fn severity(&self) -> Severity {
+        codes::lookup(self.code()).map_or(Severity::Error, |c| c.severity)
+    }
+
     /// Returns true if this is a warning rather than a hard error
     pub fn is_warning(&self) -> bool {
-        matches!(self, ValidationError::StandaloneModifier { .. })
+        self.severity() < Severity::Error
     }
+
+    /// The source location this diagnostic points at
+    pub fn span(&self) -> Span {
+        match self {
+            ValidationError::InvalidIndentation { span, .. }
+            | ValidationError::MissingFacetPrefix { span }
+            | ValidationError::MissingClaimPrefix { span }
+            | ValidationError::ConceptWithoutFacets { span, .. }
+            | ValidationError::FacetWithoutClaims { span, .. }
+            | ValidationError::OrphanFacet { span }
+            | ValidationError::OrphanClaim { span }
+            | ValidationError::EmptyClaimText { span }
+            | ValidationError::UnexpectedIndentation { span, .. }
+            | ValidationError::EmptyConceptName { span }
+            | ValidationError::EmptyFacetName { span }
+            | ValidationError::InvalidReferenceFormat { span, .. }
+            | ValidationError::UndefinedReference { span, .. }
+            | ValidationError::EmptyCondition { span }
+            | ValidationError::EmptySource { span }
+            | ValidationError::EmptyReference { span }
+            | ValidationError::UnescapedMarker { span, .. }
+            | ValidationError::MisplacedClaimElement { span, .. }
+            | ValidationError::UnreferenceableConcept { span, .. }
+            | ValidationError::InvalidIdentifier { span, .. }
+            | ValidationError::GrammarMismatch { span, .. }
+            | ValidationError::BriefFormMissingLeftOperand { span, .. }
+            | ValidationError::BriefFormMissingRightOperand { span, .. }
+            | ValidationError::UnclosedEvolutionMarker { span }
+            | ValidationError::EmptyEvolutionMarker { span }
+            | ValidationError::MalformedEvolutionMarker { span }
+            | ValidationError::StandaloneModifier { span, .. }
+            | ValidationError::DuplicateConcept { span, .. }
+            | ValidationError::DuplicateFacet { span, .. }
+            | ValidationError::DuplicateClaim { span, .. }
+            | ValidationError::CrossFileDuplicateConcept { span, .. }
+            | ValidationError::LongClaim { span, .. }
+            | ValidationError::MissingSource { span }
+            | ValidationError::InvalidDirective { span, .. }
+            | ValidationError::UnknownLint { span, .. }
+            | ValidationError::ScriptRuleFailed { span, .. }
+            | ValidationError::UnstableFormat { span }
+            | ValidationError::Custom { span, .. } => *span,
+        }
+    }
+
+    /// The 1-based line number this diagnostic points at
+    pub fn line(&self) -> usize {
+        self.span().line
+    }
+
+    /// The message without its `line N:` prefix
+    pub fn message(&self) -> String {
+        let text = self.to_string();
+        let prefix = format!("line {}: ", self.line());
+        text.strip_prefix(&prefix).unwrap_or(&text).to_string()
+    }
+
+    /// Secondary locations related to this diagnostic
+    pub fn labels(&self) -> Vec<Label> {
+        match self {
+            ValidationError::ConceptWithoutFacets { next: Some(next), .. } => vec![Label {
+                span: *next,
+                message: "expected a facet before this line".to_string(),
+            }],
+            ValidationError::FacetWithoutClaims { next: Some(next), .. } => vec![Label {
+                span: *next,
+                message: "expected a claim before this line".to_string(),
+            }],
+            ValidationError::DuplicateConcept { first, .. } | ValidationError::DuplicateFacet { first, .. } => {
+                vec![Label {
+                    span: *first,
+                    message: "first declared here".to_string(),
+                }]
+            }
+            ValidationError::DuplicateClaim { first, .. } => vec![Label {
+                span: *first,
+                message: "first stated here".to_string(),
+            }],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// A diagnostic serializes as its code, severity, message, span and labels
+#[cfg(feature = "serde")]
+impl serde::Serialize for ValidationError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("ValidationError", 5)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("severity", &self.severity())?;
+        state.serialize_field("message", &self.message())?;
+        state.serialize_field("span", &self.span())?;
+        state.serialize_field("labels", &self.labels())?;
+        state.end()
+    }
+}
+
+/// What an inline marker introduces, e.g. `a source` for `@`
+fn marker_role(marker: char) -> &'static str {
+    match marker {
+        '@' => "a source",
+        '&' => "a reference",
+        _ => "an inline element",
+    }
+}
+
+/// A `claim_order` element name for messages, e.g. `claim text`
+fn element_name(element: &str) -> String {
+    element.replace('_', " ")
+}
+
+/// What a reference target names: `concept` or `concept.facet`
+fn target_kind(reference: &str) -> &'static str {
+    if reference.contains('.') { "concept.facet" } else { "concept" }
+}
+
+/// Message suffix listing suggested names, e.g. `; did you mean 'Trust.formation'?`
+fn did_you_mean(suggestions: &[String]) -> String {
+    match suggestions {
+        [] => String::new(),
+        [only] => format!("; did you mean '{}'?", only),
+        _ => {
+            let quoted: Vec<String> = suggestions.iter().map(|s| format!("'{}'", s)).collect();
+            format!("; did you mean one of {}?", quoted.join(", "))
+        }
+    }
+}
+
+/// A secondary source location attached to a diagnostic
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
 }
 
 /// The type of a parsed line
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum LineType {
     /// Empty line or whitespace only
     Blank,
@@ -107,56 +526,187 @@ pub enum LineType {
     /// A facet (2-space indent, '.' prefix)
     Facet(String),
     /// A claim (4-space indent, '-' prefix)
-    Claim(ClaimData),
+    Claim(Box<ClaimData>),
+    /// An `#![allow(...)]` directive (unindented), with the lints it lists
+    Directive(Vec<Spanned<String>>),
 }
 
 /// Parsed claim data
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct ClaimData {
     pub text: String,
-    pub conditions: Vec<String>,
-    pub sources: Vec<String>,
-    pub references: Vec<String>,
+    pub text_span: Span,
+    pub conditions: Vec<Spanned<String>>,
+    pub sources: Vec<Spanned<String>>,
+    pub references: Vec<Spanned<Reference>>,
     pub brief_forms: Vec<BriefFormUsage>,
     pub modifiers: Vec<ModifierUsage>,
     pub evolution: Option<EvolutionMarker>,
 }
 
+/// The target of a `&` reference: a whole concept, or one of its facets
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Reference {
+    pub concept: String,
+    pub facet: Option<String>,
+}
+
+impl Reference {
+    /// Parse `Concept` or `Concept.facet` (without the leading `&`)
+    pub fn parse(target: &str) -> Self {
+        match target.split_once('.') {
+            Some((concept, facet)) => Reference {
+                concept: concept.to_string(),
+                facet: Some(facet.to_string()),
+            },
+            None => Reference {
+                concept: target.to_string(),
+                facet: None,
+            },
+        }
+    }
+
+    /// Returns true if the reference names nothing at all (a standalone `&`)
+    pub fn is_empty(&self) -> bool {
+        self.concept.is_empty() && self.facet.is_none()
+    }
+
+    /// Returns true if the concept and the facet (when present) are identifiers
+    pub fn is_well_formed(&self) -> bool {
+        is_identifier(&self.concept) && self.facet.as_deref().is_none_or(is_identifier)
+    }
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.facet {
+            Some(facet) => write!(f, "{}.{}", self.concept, facet),
+            None => f.write_str(&self.concept),
+        }
+    }
+}
+
 /// A brief form operator found in a claim
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BriefFormUsage {
     pub operator: String,
     pub left_operand: String,
     pub right_operand: String,
+    /// Location of the operator
+    pub span: Span,
 }
 
 /// A modifier found in a claim
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ModifierUsage {
     pub symbol: char,
+    /// The term the modifier inflects; empty if it follows no term
     pub attached_to: String,
+    /// Location of the modifier symbol
+    pub span: Span,
 }
 
 /// An evolution marker [<= prior belief]
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct EvolutionMarker {
     pub prior_belief: String,
+    /// Location of the whole marker, brackets included
+    pub span: Span,
 }
 
 /// A parsed line with its metadata
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParsedLine {
     pub line_number: usize,
     pub line_type: LineType,
     pub raw: String,
+    /// Location of the line content, without indentation or trailing whitespace
+    pub span: Span,
+}
+
+/// A parsed Worldview document: the Concept → Facet → Claim tree
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Document {
+    /// Lints allowed for this document by `#![allow(...)]` directives
+    pub allow: Vec<Spanned<String>>,
+    pub concepts: Vec<Concept>,
+}
+
+/// A concept (unindented) and the facets declared beneath it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Concept {
+    pub name: String,
+    /// Location of the concept line content
+    pub span: Span,
+    pub facets: Vec<Facet>,
+}
+
+/// A facet ('.' prefix) and the claims declared beneath it, in document order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Facet {
+    pub name: String,
+    /// Location of the facet line content, '.' prefix included
+    pub span: Span,
+    pub claims: Vec<Claim>,
+}
+
+/// A claim ('-' prefix) with its parsed inline elements
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Claim {
+    /// Location of the claim line content, '-' prefix included
+    pub span: Span,
+    pub data: ClaimData,
+}
+
+impl Document {
+    /// Find a concept by name (the first declaration wins)
+    pub fn concept(&self, name: &str) -> Option<&Concept> {
+        self.concepts.iter().find(|c| c.name == name)
+    }
+
+    /// Iterate over every claim in the document with its concept and facet
+    pub fn claims(&self) -> impl Iterator<Item = (&Concept, &Facet, &Claim)> {
+        self.concepts.iter().flat_map(|concept| {
+            concept.facets.iter().flat_map(move |facet| {
+                facet.claims.iter().map(move |claim| (concept, facet, claim))
+            })
+        })
+    }
+}
+
+impl Concept {
+    /// Find a facet of this concept by name (the first declaration wins)
+    pub fn facet(&self, name: &str) -> Option<&Facet> {
+        self.facets.iter().find(|f| f.name == name)
+    }
 }
 
 /// Result of validation
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ValidationResult {
     pub errors: Vec<ValidationError>,
     pub warnings: Vec<ValidationError>,
+    /// The same content as `document`, line by line, so it is not serialized
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub lines: Vec<ParsedLine>,
+    pub document: Document,
 }
 
 impl ValidationResult {
@@ -167,6 +717,21 @@ impl ValidationResult {
     pub fn has_warnings(&self) -> bool {
         !self.warnings.is_empty()
     }
+
+    /// Errors and warnings in source order, each with the severity it is reported at
+    ///
+    /// A warning denied by the lint configuration is among the errors, so it is
+    /// reported as an error here even though its code defaults to a warning.
+    pub fn diagnostics(&self) -> Vec<(Severity, &ValidationError)> {
+        let mut diagnostics: Vec<_> = self
+            .errors
+            .iter()
+            .map(|d| (Severity::Error, d))
+            .chain(self.warnings.iter().map(|d| (d.severity().min(Severity::Warning), d)))
+            .collect();
+        diagnostics.sort_by_key(|(_, d)| d.span().start);
+        diagnostics
+    }
 }
 
 impl fmt::Display for ValidationResult {
@@ -176,18 +741,18 @@ impl fmt::Display for ValidationResult {
         } else if self.is_valid() {
             writeln!(f, "Valid Worldview document with {} warning(s):", self.warnings.len())?;
             for warning in &self.warnings {
-                writeln!(f, "  {}", warning)?;
+                writeln!(f, "  [{}] {}", warning.code(), warning)?;
             }
             Ok(())
         } else {
             writeln!(f, "Invalid Worldview document ({} error(s)):", self.errors.len())?;
             for error in &self.errors {
-                writeln!(f, "  {}", error)?;
+                writeln!(f, "  [{}] {}", error.code(), error)?;
             }
             if self.has_warnings() {
                 writeln!(f, "Additionally, {} warning(s):", self.warnings.len())?;
                 for warning in &self.warnings {
-                    writeln!(f, "  {}", warning)?;
+                    writeln!(f, "  [{}] {}", warning.code(), warning)?;
                 }
             }
             Ok(())
@@ -195,37 +760,188 @@ impl fmt::Display for ValidationResult {
     }
 }
 
-/// Validates a Worldview document
-pub fn validate(input: &str) -> ValidationResult {
+/// Parses a Worldview document into its Concept → Facet → Claim tree
+///
+/// Parsing is lenient: lines that cannot be placed in the tree (bad indentation,
+/// missing prefixes, orphans) are skipped. Use [`validate`] to get diagnostics.
+pub fn parse(input: &str) -> Document {
     let mut errors = Vec::new();
-    let mut warnings = Vec::new();
-    let mut lines = Vec::new();
-
-    // First pass: tokenize lines
-    for (idx, raw_line) in input.lines().enumerate() {
-        let line_number = idx + 1;
-        let parsed = tokenize_line(raw_line, line_number, &mut errors);
-        lines.push(ParsedLine {
-            line_number,
-            line_type: parsed,
-            raw: raw_line.to_string(),
-        });
+    let lines = tokenize(input, &mut errors);
+    build_document(&lines, &mut errors)
+}
+
+/// Validates a Worldview document with the built-in rules, every lint at its default level
+///
+/// References resolve against this document alone; see [`workspace`] to
+/// validate several documents that reference each other. Use a [`Validator`]
+/// to configure lints or add rules of your own.
+pub fn validate(input: &str) -> ValidationResult {
+    Validator::new().validate(input)
+}
+
+/// Validates documents with the built-in rules, extra rules and lint levels
+///
+/// ```
+/// use worldview_validator::Validator;
+/// use worldview_validator::lint::{Level, LintConfig};
+///
+/// let mut lints = LintConfig::new();
+/// lints.set_level("missing-source", Level::Deny).unwrap();
+/// let result = Validator::new().lints(lints).validate("Trust\n  .formation\n    - slow\n");
+/// assert!(!result.is_valid());
+/// ```
+pub struct Validator {
+    rules: Vec<Box<dyn Rule>>,
+    lints: LintConfig,
+}
+
+impl Default for Validator {
+    fn default() -> Self {
+        Validator {
+            rules: rule::builtin(),
+            lints: LintConfig::default(),
+        }
+    }
+}
+
+impl Validator {
+    /// A validator with the built-in rules and default lint levels
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule, run after the built-in ones
+    pub fn rule(mut self, rule: impl Rule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Set the lint levels and options
+    pub fn lints(mut self, lints: LintConfig) -> Self {
+        self.lints = lints;
+        self
+    }
+
+    /// Check that every lint the configuration names is a rule of this validator
+    pub fn check_lints(&self) -> Result<(), LintError> {
+        for name in self.lints.configured() {
+            if !self.rules.iter().any(|rule| rule.name() == name && rule.default_level().is_some()) {
+                return Err(LintError::UnknownLint(name.to_string()));
+            }
+        }
+        Ok(())
     }
 
-    // Collect valid Concept.facet pairs for reference validation
-    let valid_refs = collect_valid_references(&lines);
+    /// The name of a lint this validator runs, given by name or by code
+    fn lint_name<'a>(&'a self, lint: &'a str) -> Option<&'a str> {
+        let name = lint::lookup(lint).map_or(lint, |l| l.name);
+        self.rules
+            .iter()
+            .any(|rule| rule.name() == name && rule.default_level().is_some())
+            .then_some(name)
+    }
+
+    /// Validate a single document
+    pub fn validate(&self, input: &str) -> ValidationResult {
+        let draft = Draft::new(input);
+        let valid_refs = collect_valid_references(&draft.document);
+        self.finish(draft, &valid_refs)
+    }
 
-    // Second pass: validate structure
-    validate_structure(&lines, &mut errors);
+    /// Run the rules over a draft, resolving references against `valid_refs`
+    pub(crate) fn finish(&self, draft: Draft, valid_refs: &HashSet<Reference>) -> ValidationResult {
+        let Draft {
+            lines,
+            document,
+            diagnostics,
+        } = draft;
+
+        // Lints the document allows for itself
+        let mut lints = Cow::Borrowed(&self.lints);
+        let mut diagnostics = diagnostics;
+        for lint in &document.allow {
+            match self.lint_name(&lint.value) {
+                // A lint this validator runs is a valid name to configure
+                Some(name) => lints.to_mut().set_level(name, Level::Allow).expect("Expected a known lint"),
+                None => diagnostics.push(ValidationError::UnknownLint {
+                    span: lint.span,
+                    lint: lint.value.clone(),
+                }),
+            }
+        }
 
-    // Third pass: validate claim syntax including brief forms, modifiers, evolution
-    for line in &lines {
-        if let LineType::Claim(claim) = &line.line_type {
-            validate_claim_syntax(line.line_number, claim, &valid_refs, &mut errors, &mut warnings);
+        let mut cx = RuleContext::new(&lints, valid_refs);
+        for diagnostic in diagnostics {
+            cx.report(diagnostic);
+        }
+
+        // Third pass: run the rules on the tree
+        rule::run(&self.rules, &document, &mut cx);
+
+        let RuleContext { errors, warnings, .. } = cx;
+
+        ValidationResult {
+            errors,
+            warnings,
+            lines,
+            document,
         }
     }
+}
 
-    ValidationResult { errors, warnings, lines }
+/// A document that has been tokenized and built into a tree, but not yet validated
+pub(crate) struct Draft {
+    pub(crate) lines: Vec<ParsedLine>,
+    pub(crate) document: Document,
+    pub(crate) diagnostics: Vec<ValidationError>,
+}
+
+impl Draft {
+    pub(crate) fn new(input: &str) -> Self {
+        let mut diagnostics = Vec::new();
+
+        // First pass: tokenize lines
+        let lines = tokenize(input, &mut diagnostics);
+
+        // Second pass: build the document tree (reports orphans)
+        let document = build_document(&lines, &mut diagnostics);
+
+        Draft {
+            lines,
+            document,
+            diagnostics,
+        }
+    }
+}
+
+/// Tokenize every line of the input, tracking byte offsets for spans
+fn tokenize(input: &str, errors: &mut Vec<ValidationError>) -> Vec<ParsedLine> {
+    let mut offset = 0;
+    input
+        .split_inclusive('\n')
+        .enumerate()
+        .map(|(idx, chunk)| {
+            let raw_line = chunk
+                .strip_suffix('\n')
+                .map(|l| l.strip_suffix('\r').unwrap_or(l))
+                .unwrap_or(chunk);
+            let line_span = Span {
+                line: idx + 1,
+                column: 1,
+                start: offset,
+                end: offset + raw_line.len(),
+            };
+            offset += chunk.len();
+
+            let (line_type, span) = tokenize_line(raw_line, line_span, errors);
+            ParsedLine {
+                line_number: line_span.line,
+                line_type,
+                raw: raw_line.to_string(),
+                span,
+            }
+        })
+        .collect()
 }
 
 /// Count leading spaces
@@ -233,21 +949,66 @@ fn count_leading_spaces(line: &str) -> usize {
     line.chars().take_while(|c| *c == ' ').count()
 }
 
-/// Tokenize a single line
-fn tokenize_line(line: &str, line_number: usize, errors: &mut Vec<ValidationError>) -> LineType {
+/// Byte range of `text[range]` with surrounding whitespace trimmed
+fn trim_range(text: &str, range: Range<usize>) -> Range<usize> {
+    let slice = &text[range.clone()];
+    let start = range.start + (slice.len() - slice.trim_start().len());
+    let end = range.end - (slice.len() - slice.trim_end().len());
+    if start > end { start..start } else { start..end }
+}
+
+/// The lints an `#![allow(lint, ...)]` directive lists, or `None` if it is malformed
+fn parse_directive(content: &str, span: Span) -> Option<Vec<Spanned<String>>> {
+    const PREFIX: &str = "#![allow(";
+    let list = content.strip_prefix(PREFIX)?.strip_suffix(")]")?;
+    let mut lints = Vec::new();
+    let mut offset = PREFIX.len();
+    for item in list.split(',') {
+        let range = trim_range(content, offset..offset + item.len());
+        let name = &content[range.clone()];
+        if !is_identifier(name) {
+            return None;
+        }
+        lints.push(Spanned {
+            value: name.to_string(),
+            span: span.subspan(content, range),
+        });
+        offset += item.len() + 1;
+    }
+    Some(lints)
+}
+
+/// Tokenize a single line, returning its type and the span of its content
+fn tokenize_line(line: &str, line_span: Span, errors: &mut Vec<ValidationError>) -> (LineType, Span) {
+    let content_range = trim_range(line, 0..line.len());
+    let content_span = line_span.subspan(line, content_range.clone());
+
     // Blank lines
-    if line.trim().is_empty() {
-        return LineType::Blank;
+    if content_range.is_empty() {
+        return (LineType::Blank, content_span);
     }
 
     let indent = count_leading_spaces(line);
-    let content = line.trim();
-
-    match indent {
+    let content = &line[content_range.clone()];
+
+    let line_type = match indent {
+        0 if content.starts_with("#!") => {
+            // Directive: no indent, '#!' prefix
+            match parse_directive(content, content_span) {
+                Some(lints) => LineType::Directive(lints),
+                None => {
+                    errors.push(ValidationError::InvalidDirective {
+                        span: content_span,
+                        reason: "malformed directive (expected '#![allow(lint, ...)]')",
+                    });
+                    LineType::Blank
+                }
+            }
+        }
         0 => {
             // Concept: no indent, bare text
             if content.is_empty() {
-                errors.push(ValidationError::EmptyConceptName { line: line_number });
+                errors.push(ValidationError::EmptyConceptName { span: content_span });
                 LineType::Blank
             } else {
                 LineType::Concept(content.to_string())
@@ -256,12 +1017,12 @@ fn tokenize_line(line: &str, line_number: usize, errors: &mut Vec<ValidationErro
         2 => {
             // Facet: 2-space indent, '.' prefix
             if !content.starts_with('.') {
-                errors.push(ValidationError::MissingFacetPrefix { line: line_number });
+                errors.push(ValidationError::MissingFacetPrefix { span: content_span });
                 LineType::Blank
             } else {
                 let name = content[1..].trim();
                 if name.is_empty() {
-                    errors.push(ValidationError::EmptyFacetName { line: line_number });
+                    errors.push(ValidationError::EmptyFacetName { span: content_span });
                 }
                 LineType::Facet(name.to_string())
             }
@@ -269,126 +1030,100 @@ fn tokenize_line(line: &str, line_number: usize, errors: &mut Vec<ValidationErro
         4 => {
             // Claim: 4-space indent, '-' prefix
             if !content.starts_with('-') {
-                errors.push(ValidationError::MissingClaimPrefix { line: line_number });
+                errors.push(ValidationError::MissingClaimPrefix { span: content_span });
                 LineType::Blank
             } else {
-                let claim_text = content[1..].trim();
-                let claim_data = parse_claim(claim_text);
-                LineType::Claim(claim_data)
+                let text_range = trim_range(content, 1..content.len());
+                let text_span = content_span.subspan(content, text_range.clone());
+                let claim_data = parse_claim(&content[text_range], text_span, errors);
+                LineType::Claim(Box::new(claim_data))
             }
         }
         _ => {
             // Invalid indentation
+            let indent_span = line_span.subspan(line, 0..indent);
             if indent == 1 || indent == 3 {
                 errors.push(ValidationError::InvalidIndentation {
-                    line: line_number,
+                    span: indent_span,
                     expected: "0, 2, or 4",
                     found: indent,
                 });
             } else {
                 errors.push(ValidationError::UnexpectedIndentation {
-                    line: line_number,
+                    span: indent_span,
                     found: indent,
                 });
             }
             LineType::Blank
         }
-    }
+    };
+
+    (line_type, content_span)
 }
 
 /// Parse claim content into structured data
-fn parse_claim(text: &str) -> ClaimData {
-    let mut claim_text = String::new();
+///
+/// `span` is the location of `text` in the document; every extracted element
+/// gets a span relative to it. Markers that should have been escaped and
+/// elements out of [`CLAIM_ORDER`] are reported.
+fn parse_claim(text: &str, span: Span, errors: &mut Vec<ValidationError>) -> ClaimData {
+    let tokens = lexer::lex_claim(text, span);
+
+    // A closed evolution marker is an element of its own; an unclosed one stays in the text
+    let evolution_marker = closed_evolution(&tokens, span);
+    let evolution = evolution_marker.as_ref().map(|(_, range)| EvolutionMarker {
+        prior_belief: text[range.start + 3..range.end - 1].trim().to_string(),
+        span: span.subspan(text, range.clone()),
+    });
+    let evolution_tokens = evolution_marker.map(|(tokens, _)| tokens);
+
+    let parts = split_claim(text, span, &tokens, evolution_tokens.as_ref(), errors);
+    check_claim_order(&parts, text, span, errors);
+
+    // The claim text is the leading text part; text elsewhere is out of order
+    let claim_text = match parts.first() {
+        Some(part) if part.prefix.is_none() => part.range.clone(),
+        Some(part) => part.range.start..part.range.start,
+        None => text.len()..text.len(),
+    };
+    let text_span = span.subspan(text, claim_text.clone());
+
     let mut conditions = Vec::new();
     let mut sources = Vec::new();
     let mut references = Vec::new();
-
-    // First, extract evolution marker if present
-    let (text_without_evolution, evolution) = extract_evolution_marker(text);
-    let text = text_without_evolution.as_str();
-
-    // Parse inline elements (|, @, &)
-    let mut current_segment = String::new();
-    let mut in_claim = true;
-    let mut chars = text.chars().peekable();
-
-    while let Some(c) = chars.next() {
-        match c {
-            '|' => {
-                // Condition marker
-                if in_claim {
-                    claim_text = current_segment.trim().to_string();
-                    in_claim = false;
-                } else if !current_segment.trim().is_empty() {
-                    conditions.push(current_segment.trim().to_string());
-                }
-                current_segment = String::new();
-            }
-            '@' => {
-                // Source marker
-                if in_claim {
-                    claim_text = current_segment.trim().to_string();
-                    in_claim = false;
-                } else if !current_segment.trim().is_empty() {
-                    conditions.push(current_segment.trim().to_string());
-                }
-                current_segment = String::new();
-                // Collect source name (until space or another marker)
-                while let Some(&next) = chars.peek() {
-                    if next == ' ' || next == '|' || next == '@' || next == '&' {
-                        break;
-                    }
-                    current_segment.push(chars.next().unwrap());
-                }
-                if !current_segment.trim().is_empty() {
-                    sources.push(current_segment.trim().to_string());
-                }
-                current_segment = String::new();
-            }
-            '&' => {
-                // Reference marker
-                if in_claim {
-                    claim_text = current_segment.trim().to_string();
-                    in_claim = false;
-                } else if !current_segment.trim().is_empty() {
-                    conditions.push(current_segment.trim().to_string());
-                }
-                current_segment = String::new();
-                // Collect reference (until space or another marker)
-                while let Some(&next) = chars.peek() {
-                    if next == ' ' || next == '|' || next == '@' || next == '&' {
-                        break;
-                    }
-                    current_segment.push(chars.next().unwrap());
-                }
-                if !current_segment.trim().is_empty() {
-                    references.push(current_segment.trim().to_string());
-                }
-                current_segment = String::new();
-            }
-            _ => {
-                current_segment.push(c);
-            }
-        }
-    }
-
-    // Handle remaining segment
-    if !current_segment.trim().is_empty() {
-        if in_claim {
-            claim_text = current_segment.trim().to_string();
-        } else {
-            conditions.push(current_segment.trim().to_string());
+    for part in &parts {
+        let Some(prefix) = part.prefix else { continue };
+        let value = &text[part.range.start + prefix.len_utf8()..part.range.end];
+        let element_span = span.subspan(text, part.range.clone());
+        match prefix {
+            '|' => conditions.push(Spanned {
+                value: value.trim().to_string(),
+                span: element_span,
+            }),
+            '@' => sources.push(Spanned {
+                value: value.to_string(),
+                span: element_span,
+            }),
+            '&' => references.push(Spanned {
+                value: Reference::parse(value),
+                span: element_span,
+            }),
+            // The evolution marker, read above
+            _ => {}
         }
     }
 
-    // Extract brief forms from claim text
-    let brief_forms = extract_brief_forms(&claim_text);
-
-    // Extract modifiers from claim text
-    let modifiers = extract_modifiers(&claim_text);
+    // Brief forms and modifiers are read from the claim text only
+    let text_tokens: Vec<&Token> = tokens
+        .iter()
+        .filter(|t| claim_text.contains(&(t.span.start - span.start)))
+        .collect();
+    let brief_forms = extract_brief_forms(&text_tokens);
+    let modifiers = extract_modifiers(&text_tokens);
 
     ClaimData {
-        text: claim_text,
+        text: text[claim_text].to_string(),
+        text_span,
         conditions,
         sources,
         references,
@@ -398,357 +1133,357 @@ fn parse_claim(text: &str) -> ClaimData {
     }
 }
 
-/// Extract evolution marker [<= prior belief] from text
-fn extract_evolution_marker(text: &str) -> (String, Option<EvolutionMarker>) {
-    if let Some(start) = text.find("[<=") {
-        if let Some(end) = text[start..].find(']') {
-            let marker_content = &text[start + 3..start + end];
-            let prior_belief = marker_content.trim().to_string();
-            let text_before = &text[..start];
-            let text_after = &text[start + end + 1..];
-            let cleaned = format!("{}{}", text_before.trim(), text_after.trim());
-            return (
-                cleaned,
-                Some(EvolutionMarker { prior_belief }),
-            );
-        }
-        // Unclosed marker - return as-is, validation will catch it
-        return (text.to_string(), None);
-    }
-    (text.to_string(), None)
-}
-
-/// Extract brief form usages from claim text
-fn extract_brief_forms(text: &str) -> Vec<BriefFormUsage> {
-    let mut usages = Vec::new();
-
-    // Check for each brief form operator
-    // Order matters: check longer operators first to avoid partial matches
-    // Minimal set: =>, vs, ~, =
-    let operators_by_length: &[&str] = &["=>", "vs", "~", "="];
-
-    let remaining = text.to_string();
-
-    for &op in operators_by_length {
-        // Special handling for = to avoid matching =>
-        if op == "=" {
-            // Look for standalone = not part of =>
-            let mut i = 0;
-            let chars: Vec<char> = remaining.chars().collect();
-            while i < chars.len() {
-                if chars[i] == '=' {
-                    let prev = if i > 0 { Some(chars[i - 1]) } else { None };
-                    let next = chars.get(i + 1);
-                    // Check it's not part of =>
-                    if prev != Some('<') && prev != Some('>') && next != Some(&'>') {
-                        // Found standalone =
-                        let before: String = chars[..i].iter().collect();
-                        let after: String = chars[i + 1..].iter().collect();
-                        let left = before.split_whitespace().last().unwrap_or("").to_string();
-                        let right = after.split_whitespace().next().unwrap_or("").to_string();
-                        if !left.is_empty() || !right.is_empty() {
-                            usages.push(BriefFormUsage {
-                                operator: op.to_string(),
-                                left_operand: left,
-                                right_operand: right,
-                            });
-                        }
-                    }
-                }
-                i += 1;
-            }
-            continue;
-        }
-
-        // For other operators
-        for (idx, _) in remaining.match_indices(op) {
-            let before = &remaining[..idx];
-            let after = &remaining[idx + op.len()..];
-
-            let left = before.split_whitespace().last().unwrap_or("").to_string();
-            let right = after.split_whitespace().next().unwrap_or("").to_string();
-
-            // Clean up modifiers from operands for matching
-            let left_clean = left.trim_end_matches(|c| "^v!?*".contains(c));
-            let right_clean = right.trim_end_matches(|c| "^v!?*".contains(c));
+/// One element of a claim: the claim text, a condition, a source, a reference
+/// or the evolution marker
+#[derive(Debug, Clone)]
+struct ClaimPart {
+    /// The element's prefix, `None` for claim text
+    prefix: Option<char>,
+    /// Byte range in the claim, including the prefix
+    range: Range<usize>,
+}
 
-            usages.push(BriefFormUsage {
-                operator: op.to_string(),
-                left_operand: left_clean.to_string(),
-                right_operand: right_clean.to_string(),
-            });
-        }
+impl ClaimPart {
+    /// Position of this part's element in [`CLAIM_ORDER`]
+    fn rank(&self) -> usize {
+        CLAIM_ORDER
+            .iter()
+            .position(|(_, prefix)| *prefix == self.prefix)
+            .unwrap_or(0)
     }
 
-    usages
+    /// The element's name in [`CLAIM_ORDER`], e.g. `conditions`
+    fn element(&self) -> &'static str {
+        CLAIM_ORDER.get(self.rank()).map_or("claim_text", |(name, _)| name)
+    }
 }
 
-/// Extract modifier usages from claim text
-fn extract_modifiers(text: &str) -> Vec<ModifierUsage> {
-    let mut usages = Vec::new();
-    let modifier_chars = ['^', '!', '?', '*'];
-
-    // Split into tokens
-    let tokens: Vec<&str> = text.split_whitespace().collect();
+/// The first closed evolution marker: its token indices and its byte range in the claim
+fn closed_evolution(tokens: &[Token], span: Span) -> Option<(RangeInclusive<usize>, Range<usize>)> {
+    let open = tokens.iter().position(|t| t.kind == TokenKind::EvolutionOpen)?;
+    let close = open + tokens[open..].iter().position(|t| t.kind == TokenKind::EvolutionClose)?;
+    let range = tokens[open].span.start - span.start..tokens[close].span.end - span.start;
+    Some((open..=close, range))
+}
 
-    for (i, token) in tokens.iter().enumerate() {
-        // Check for attached modifiers (e.g., "concentration^", "collapse?")
-        for &m in &modifier_chars {
-            if token.ends_with(m) && token.len() > 1 {
-                let attached = token.trim_end_matches(m);
-                usages.push(ModifierUsage {
-                    symbol: m,
-                    attached_to: attached.to_string(),
+/// Split claim content into its parts, in source order
+///
+/// The tokens in `evolution` (a closed evolution marker) make up a single part.
+/// Markers that should have been escaped are reported and kept as text.
+fn split_claim(
+    text: &str,
+    span: Span,
+    tokens: &[Token],
+    evolution: Option<&RangeInclusive<usize>>,
+    errors: &mut Vec<ValidationError>,
+) -> Vec<ClaimPart> {
+    let start = |t: &Token| t.span.start - span.start;
+    let end = |t: &Token| t.span.end - span.start;
+
+    let mut parts = Vec::new();
+    // The open text or condition part, which runs until the next marker
+    let mut open: Option<ClaimPart> = None;
+    let mut iter = tokens.iter().enumerate().peekable();
+    while let Some((idx, token)) = iter.next() {
+        if let Some(marker) = evolution.filter(|r| r.contains(&idx)) {
+            if idx == *marker.start() {
+                parts.extend(open.take());
+                parts.push(ClaimPart {
+                    prefix: token.text.chars().next(),
+                    range: start(token)..end(&tokens[*marker.end()]),
                 });
             }
+            continue;
         }
-
-        // Check for standalone modifiers (e.g., "fast !" where ! is separate token)
-        // These modify the preceding term
-        for &m in &modifier_chars {
-            if token.len() == 1 && token.chars().next() == Some(m) && i > 0 {
-                let prev = tokens[i - 1];
-                // Don't count if previous token is an operator
-                let is_after_operator = BRIEF_FORMS.iter().any(|(op, _)| prev.ends_with(op));
-                if !is_after_operator {
-                    usages.push(ModifierUsage {
-                        symbol: m,
-                        attached_to: prev.trim_end_matches(|c| "^!?*v".contains(c)).to_string(),
+        match token.kind {
+            TokenKind::ConditionMarker => {
+                let condition = ClaimPart {
+                    prefix: token.text.chars().next(),
+                    range: start(token)..end(token),
+                };
+                parts.extend(open.replace(condition));
+            }
+            TokenKind::SourceMarker | TokenKind::RefMarker => {
+                let name_kind = if token.kind == TokenKind::SourceMarker {
+                    TokenKind::Source
+                } else {
+                    TokenKind::Reference
+                };
+                let name = iter.next_if(|(_, t)| t.kind == name_kind).map(|(_, t)| t);
+                let range = start(token)..name.map_or(end(token), end);
+                if !starts_element(text, start(token), name) {
+                    errors.push(ValidationError::UnescapedMarker {
+                        span: token.span,
+                        marker: token.text.chars().next().unwrap_or_default(),
                     });
+                    extend_part(&mut open, range);
+                    continue;
                 }
-            }
-        }
-
-        // Check for 'v' modifier - it's special because it's also a letter
-        // It's a modifier when: standalone 'v' following a term
-        if *token == "v" && i > 0 {
-            let prev = tokens[i - 1];
-            // Don't treat 'v' as modifier if previous token is an operator
-            let is_after_operator = BRIEF_FORMS.iter().any(|(op, _)| prev.ends_with(op));
-            if !is_after_operator {
-                usages.push(ModifierUsage {
-                    symbol: 'v',
-                    attached_to: prev.trim_end_matches(|c| "^!?*".contains(c)).to_string(),
+                parts.extend(open.take());
+                parts.push(ClaimPart {
+                    prefix: token.text.chars().next(),
+                    range,
                 });
             }
+            _ => extend_part(&mut open, start(token)..end(token)),
         }
     }
-
-    usages
+    parts.extend(open);
+    parts
 }
 
-/// Validate document structure
-fn validate_structure(lines: &[ParsedLine], errors: &mut Vec<ValidationError>) {
-    let mut current_concept: Option<(usize, String)> = None;
-    let mut current_facet: Option<(usize, String)> = None;
-    let mut concept_has_facet = false;
-    let mut facet_has_claim = false;
+/// Grow the open part to cover `range`, starting a text part if none is open
+fn extend_part(open: &mut Option<ClaimPart>, range: Range<usize>) {
+    let part = open.get_or_insert_with(|| ClaimPart {
+        prefix: None,
+        range: range.clone(),
+    });
+    part.range.end = range.end;
+}
 
-    for line in lines {
-        match &line.line_type {
-            LineType::Blank => continue,
-            LineType::Concept(name) => {
-                // Check previous concept had facets
-                if let Some((concept_line, concept_name)) = current_concept.take() {
-                    if !concept_has_facet {
-                        errors.push(ValidationError::ConceptWithoutFacets {
-                            line: concept_line,
-                            concept: concept_name,
-                        });
-                    }
-                }
-                // Check previous facet had claims
-                if let Some((facet_line, facet_name)) = current_facet.take() {
-                    if !facet_has_claim {
-                        errors.push(ValidationError::FacetWithoutClaims {
-                            line: facet_line,
-                            facet: facet_name,
-                        });
-                    }
-                }
-                current_concept = Some((line.line_number, name.clone()));
-                current_facet = None;
-                concept_has_facet = false;
-                facet_has_claim = false;
-            }
-            LineType::Facet(name) => {
-                if current_concept.is_none() {
-                    errors.push(ValidationError::OrphanFacet {
-                        line: line.line_number,
-                    });
-                } else {
-                    concept_has_facet = true;
-                }
-                // Check previous facet had claims
-                if let Some((facet_line, facet_name)) = current_facet.take() {
-                    if !facet_has_claim {
-                        errors.push(ValidationError::FacetWithoutClaims {
-                            line: facet_line,
-                            facet: facet_name,
-                        });
-                    }
-                }
-                current_facet = Some((line.line_number, name.clone()));
-                facet_has_claim = false;
-            }
-            LineType::Claim(_) => {
-                if current_facet.is_none() {
-                    errors.push(ValidationError::OrphanClaim {
-                        line: line.line_number,
-                    });
-                } else {
-                    facet_has_claim = true;
-                }
-            }
+/// Report parts that come after an element they should precede in [`CLAIM_ORDER`]
+fn check_claim_order(parts: &[ClaimPart], text: &str, span: Span, errors: &mut Vec<ValidationError>) {
+    let mut latest: Option<&ClaimPart> = None;
+    for part in parts {
+        match latest {
+            Some(prev) if prev.rank() > part.rank() => errors.push(ValidationError::MisplacedClaimElement {
+                span: span.subspan(text, part.range.clone()),
+                element: part.element(),
+                after: prev.element(),
+                claim: span,
+            }),
+            _ => latest = Some(part),
         }
     }
+}
 
-    // Check final concept and facet
-    if let Some((concept_line, concept_name)) = current_concept {
-        if !concept_has_facet {
-            errors.push(ValidationError::ConceptWithoutFacets {
-                line: concept_line,
-                concept: concept_name,
-            });
-        }
-    }
-    if let Some((facet_line, facet_name)) = current_facet {
-        if !facet_has_claim {
-            errors.push(ValidationError::FacetWithoutClaims {
-                line: facet_line,
-                facet: facet_name,
-            });
-        }
-    }
+/// Claim content rewritten with its elements in [`CLAIM_ORDER`]
+///
+/// Also returns whether claim text had to move, which may change what the
+/// claim says: in `slow @history mostly`, `mostly` joins the claim text.
+pub(crate) fn reorder_claim(text: &str, span: Span) -> (String, bool) {
+    let tokens = lexer::lex_claim(text, span);
+    let evolution = closed_evolution(&tokens, span).map(|(tokens, _)| tokens);
+    let mut parts = split_claim(text, span, &tokens, evolution.as_ref(), &mut Vec::new());
+    let moves_text = parts.iter().skip(1).any(|part| part.prefix.is_none());
+    parts.sort_by_key(ClaimPart::rank);
+
+    let pieces: Vec<&str> = parts.iter().map(|part| text[part.range.clone()].trim()).collect();
+    (pieces.join(" "), moves_text)
 }
 
-/// Collect all valid Concept.facet reference targets from the document
-fn collect_valid_references(lines: &[ParsedLine]) -> HashSet<String> {
-    let mut valid_refs = HashSet::new();
-    let mut current_concept: Option<String> = None;
+/// Whether the `@` or `&` at `at` starts an inline element
+///
+/// A marker glued to the word before it (`AT&T`, `ops@corp`) or followed by
+/// something that cannot be a name (`x & y`) is stray text that needs escaping.
+/// A marker with nothing after it is an empty element, reported separately.
+fn starts_element(text: &str, at: usize, name: Option<&Token>) -> bool {
+    let glued = text[..at].chars().next_back().is_some_and(char::is_alphanumeric);
+    let nameable = match name {
+        Some(name) => name.text.starts_with(|c: char| c.is_alphanumeric() || matches!(c, '_' | '.')),
+        None => text[at + 1..]
+            .trim_start()
+            .chars()
+            .next()
+            .is_none_or(|c| matches!(c, '|' | '@' | '&' | '[')),
+    };
+    !glued && nameable
+}
+
+/// The word a modifier or operator at `tokens[idx]` applies to, looking back
+///
+/// Modifiers in between are skipped; anything else means there is no term.
+fn term_before<'a>(tokens: &[&Token<'a>], idx: usize) -> &'a str {
+    tokens[..idx]
+        .iter()
+        .rev()
+        .find(|t| t.kind != TokenKind::Modifier)
+        .filter(|t| t.kind == TokenKind::Word)
+        .map_or("", |t| t.text)
+}
+
+/// The word after the operator at `tokens[idx]`, skipping modifiers
+fn term_after<'a>(tokens: &[&Token<'a>], idx: usize) -> &'a str {
+    tokens[idx + 1..]
+        .iter()
+        .find(|t| t.kind != TokenKind::Modifier)
+        .filter(|t| t.kind == TokenKind::Word)
+        .map_or("", |t| t.text)
+}
+
+/// Extract brief form usages from the claim text's tokens
+fn extract_brief_forms(tokens: &[&Token]) -> Vec<BriefFormUsage> {
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| t.kind == TokenKind::Operator)
+        .map(|(idx, t)| BriefFormUsage {
+            operator: t.text.to_string(),
+            left_operand: term_before(tokens, idx).to_string(),
+            right_operand: term_after(tokens, idx).to_string(),
+            span: t.span,
+        })
+        .collect()
+}
+
+/// Extract modifier usages from the claim text's tokens
+fn extract_modifiers(tokens: &[&Token]) -> Vec<ModifierUsage> {
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| t.kind == TokenKind::Modifier)
+        .map(|(idx, t)| ModifierUsage {
+            symbol: t.text.chars().next().unwrap_or_default(),
+            attached_to: term_before(tokens, idx).to_string(),
+            span: t.span,
+        })
+        .collect()
+}
+
+/// Build the Concept → Facet → Claim tree from tokenized lines
+fn build_document(lines: &[ParsedLine], errors: &mut Vec<ValidationError>) -> Document {
+    let mut document = Document::default();
+    // Claims under an orphan facet are already covered by the OrphanFacet error
+    let mut in_orphan_facet = false;
 
     for line in lines {
         match &line.line_type {
+            LineType::Blank => continue,
+            LineType::Directive(lints) if document.concepts.is_empty() => {
+                document.allow.extend(lints.iter().cloned());
+            }
+            LineType::Directive(_) => errors.push(ValidationError::InvalidDirective {
+                span: line.span,
+                reason: "directives must come before the first concept",
+            }),
             LineType::Concept(name) => {
-                current_concept = Some(name.clone());
+                document.concepts.push(Concept {
+                    name: name.clone(),
+                    span: line.span,
+                    facets: Vec::new(),
+                });
+                in_orphan_facet = false;
             }
-            LineType::Facet(name) => {
-                if let Some(ref concept) = current_concept {
-                    valid_refs.insert(format!("{}.{}", concept, name));
+            LineType::Facet(name) => match document.concepts.last_mut() {
+                Some(concept) => concept.facets.push(Facet {
+                    name: name.clone(),
+                    span: line.span,
+                    claims: Vec::new(),
+                }),
+                None => {
+                    errors.push(ValidationError::OrphanFacet { span: line.span });
+                    in_orphan_facet = true;
+                }
+            },
+            LineType::Claim(data) => {
+                let facet = document
+                    .concepts
+                    .last_mut()
+                    .and_then(|concept| concept.facets.last_mut());
+                match facet {
+                    Some(facet) => facet.claims.push(Claim {
+                        span: line.span,
+                        data: data.as_ref().clone(),
+                    }),
+                    None if in_orphan_facet => {}
+                    None => errors.push(ValidationError::OrphanClaim { span: line.span }),
                 }
             }
-            _ => {}
         }
     }
 
-    valid_refs
+    document
 }
 
-/// Validate claim syntax including brief forms, modifiers, and evolution markers
-fn validate_claim_syntax(
-    line_number: usize,
-    claim: &ClaimData,
-    valid_refs: &HashSet<String>,
-    errors: &mut Vec<ValidationError>,
-    warnings: &mut Vec<ValidationError>,
-) {
-    // Check for empty claim text
-    if claim.text.is_empty() {
-        errors.push(ValidationError::EmptyClaimText { line: line_number });
-    }
-
-    // Check for empty conditions
-    for cond in &claim.conditions {
-        if cond.is_empty() {
-            errors.push(ValidationError::EmptyCondition { line: line_number });
-        }
-    }
-
-    // Check for empty sources
-    for src in &claim.sources {
-        if src.is_empty() {
-            errors.push(ValidationError::EmptySource { line: line_number });
-        }
-    }
-
-    // Check for empty references
-    for reference in &claim.references {
-        if reference.is_empty() {
-            errors.push(ValidationError::EmptyReference { line: line_number });
-        }
-    }
-
-    // Validate reference format (should be Concept.facet)
-    for reference in &claim.references {
-        if !reference.is_empty() && !reference.contains('.') {
-            errors.push(ValidationError::InvalidReferenceFormat {
-                line: line_number,
-                reference: reference.clone(),
-            });
-        }
-    }
-
-    // Validate references point to existing concept.facet pairs
-    for reference in &claim.references {
-        if !reference.is_empty() && reference.contains('.') && !valid_refs.contains(reference) {
-            errors.push(ValidationError::UndefinedReference {
-                line: line_number,
-                reference: reference.clone(),
-            });
-        }
-    }
+/// Whether `name` is a valid identifier: ASCII letters, digits, `-` and `_`
+///
+/// Facet and source names must be identifiers, and only concepts named by one
+/// can be referenced.
+pub fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+}
 
-    // Validate brief forms have operands
-    for bf in &claim.brief_forms {
-        if bf.left_operand.is_empty() {
-            errors.push(ValidationError::BriefFormMissingLeftOperand {
-                line: line_number,
-                operator: bf.operator.clone(),
-            });
-        }
-        if bf.right_operand.is_empty() {
-            errors.push(ValidationError::BriefFormMissingRightOperand {
-                line: line_number,
-                operator: bf.operator.clone(),
-            });
-        }
-    }
+/// Collect all valid reference targets from the document: each concept, and each of its facets
+pub(crate) fn collect_valid_references(document: &Document) -> HashSet<Reference> {
+    document
+        .concepts
+        .iter()
+        .flat_map(|concept| {
+            let whole = Reference {
+                concept: concept.name.clone(),
+                facet: None,
+            };
+            concept.facets.iter().map(move |facet| Reference {
+                concept: concept.name.clone(),
+                facet: Some(facet.name.clone()),
+            }).chain(std::iter::once(whole))
+        })
+        .collect()
+}
 
-    // Check for unclosed evolution markers in original text
-    if claim.text.contains("[<=") && !claim.text.contains(']') {
-        errors.push(ValidationError::UnclosedEvolutionMarker { line: line_number });
+/// Most suggestions offered for a misspelled reference
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Defined reference targets that `reference` is probably a misspelling of
+///
+/// A target differing only in case is the only suggestion. Otherwise
+/// candidates are ranked by case-insensitive edit distance, and distant
+/// candidates are not offered.
+///
+/// Only targets of the same kind are considered: a concept reference is never
+/// corrected to a facet reference, or vice versa.
+pub(crate) fn suggest_references(reference: &Reference, valid_refs: &HashSet<Reference>) -> Vec<String> {
+    let reference = reference.to_string();
+    let candidates: Vec<String> = valid_refs
+        .iter()
+        .filter(|candidate| target_kind(&candidate.to_string()) == target_kind(&reference))
+        .map(|candidate| candidate.to_string())
+        .collect();
+
+    let mut case_matches: Vec<String> = candidates
+        .iter()
+        .filter(|candidate| candidate.eq_ignore_ascii_case(&reference))
+        .cloned()
+        .collect();
+    if !case_matches.is_empty() {
+        case_matches.sort();
+        return case_matches;
     }
 
-    // Validate evolution marker content if present
-    if let Some(ref evo) = claim.evolution {
-        if evo.prior_belief.is_empty() {
-            errors.push(ValidationError::EmptyEvolutionMarker { line: line_number });
-        }
-    }
+    let wanted = reference.to_lowercase();
+    let threshold = (wanted.chars().count() / 3).max(1);
+    let mut candidates: Vec<(usize, &String)> = candidates
+        .iter()
+        .map(|candidate| (edit_distance(&wanted, &candidate.to_lowercase()), candidate))
+        .filter(|&(distance, _)| distance <= threshold)
+        .collect();
+    candidates.sort();
+    candidates
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate.clone())
+        .collect()
+}
 
-    // Check for standalone modifiers that appear at the start (warning, not error)
-    // Space-separated modifiers that follow a term are valid (e.g., "fast !")
-    let tokens: Vec<&str> = claim.text.split_whitespace().collect();
-    for (i, token) in tokens.iter().enumerate() {
-        if *token == "^" || *token == "!" || *token == "?" || *token == "*" {
-            // Only warn if it's at the start (no preceding term) or follows an operator
-            if i == 0 {
-                warnings.push(ValidationError::StandaloneModifier {
-                    line: line_number,
-                    modifier: token.to_string(),
-                });
-            } else {
-                let prev = tokens[i - 1];
-                let is_after_operator = BRIEF_FORMS.iter().any(|(op, _)| prev.ends_with(op));
-                if is_after_operator {
-                    warnings.push(ValidationError::StandaloneModifier {
-                        line: line_number,
-                        modifier: token.to_string(),
-                    });
-                }
+/// Edit distance between two strings, in characters
+///
+/// Counts insertions, deletions, substitutions and transpositions of adjacent
+/// characters (optimal string alignment), so `Trsut` is one edit from `Trust`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d: Vec<Vec<usize>> = (0..=a.len()).map(|i| vec![i; b.len() + 1]).collect();
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
             }
         }
     }
+    d[a.len()][b.len()]
 }
 
 /// Validate a file by path
@@ -758,9 +1493,49 @@ pub fn validate_file(path: &std::path::Path) -> Result<ValidationResult, std::io
 }
 
 #[cfg(test)]
+// The claim tests find their line and then match its type, as two steps
+#[allow(clippy::collapsible_if)]
 mod tests {
     use super::*;
 
+    /// The first claim in the validated document
+    fn first_claim(result: &ValidationResult) -> &ClaimData {
+        &result.document.claims().next().expect("Expected a claim").2.data
+    }
+
+    /// One directory of the shared corpus in spec/fixtures
+    pub(crate) fn fixture_dir(kind: &str) -> std::path::PathBuf {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../spec/fixtures").join(kind)
+    }
+
+    /// The documents in one directory of the shared corpus, by file name
+    pub(crate) fn fixtures(kind: &str) -> Vec<(String, String)> {
+        let dir = fixture_dir(kind);
+        let mut fixtures: Vec<_> = std::fs::read_dir(&dir)
+            .expect("Expected a fixture directory")
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "wvf"))
+            .map(|path| {
+                let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                (name, std::fs::read_to_string(&path).unwrap())
+            })
+            .collect();
+        fixtures.sort();
+        assert!(!fixtures.is_empty(), "no fixtures in {}", dir.display());
+        fixtures
+    }
+
+    #[test]
+    fn test_fixture_corpus() {
+        for (name, source) in fixtures("valid") {
+            let result = validate(&source);
+            assert!(result.is_valid(), "{name} should be valid: {:?}", result.errors);
+        }
+        for (name, source) in fixtures("invalid").into_iter().chain(fixtures("semantic")) {
+            assert!(!validate(&source).is_valid(), "{name} should be invalid");
+        }
+    }
+
     // ==================== Structural tests ====================
 
     #[test]
@@ -850,6 +1625,223 @@ Trust
         assert!(result.is_valid(), "Expected valid: {:?}", result.errors);
     }
 
+    // ==================== Document tree tests ====================
+
+    #[test]
+    fn test_parse_builds_tree() {
+        let input = r#"Power
+  .core
+    - corrupts | unchecked
+    - reveals character
+
+Trust
+  .formation
+    - slow
+  .erosion
+    - fast !"#;
+
+        let doc = parse(input);
+        assert_eq!(doc.concepts.len(), 2);
+
+        let power = doc.concept("Power").expect("Expected Power concept");
+        assert_eq!(power.span.line, 1);
+        assert_eq!(power.facets.len(), 1);
+        let core = power.facet("core").expect("Expected .core facet");
+        assert_eq!(core.span.line, 2);
+        let texts: Vec<_> = core.claims.iter().map(|c| c.data.text.as_str()).collect();
+        assert_eq!(texts, ["corrupts", "reveals character"]);
+        assert_eq!(core.claims[0].data.conditions[0].value, "unchecked");
+
+        let trust = doc.concept("Trust").expect("Expected Trust concept");
+        let facets: Vec<_> = trust.facets.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(facets, ["formation", "erosion"]);
+        assert_eq!(trust.facet("erosion").unwrap().claims[0].span.line, 10);
+    }
+
+    #[test]
+    fn test_parse_skips_orphans() {
+        let input = r#"  .core
+    - corrupts
+Power
+    - orphan
+  .core
+    - reveals character"#;
+
+        let doc = parse(input);
+        assert_eq!(doc.concepts.len(), 1);
+        assert_eq!(doc.claims().count(), 1);
+
+        // Claims under an orphan facet are not reported a second time
+        let result = validate(input);
+        assert_eq!(result.errors.len(), 2, "{:?}", result.errors);
+        assert!(matches!(result.errors[0], ValidationError::OrphanFacet { .. }));
+        assert_eq!(result.errors[0].line(), 1);
+        assert!(matches!(result.errors[1], ValidationError::OrphanClaim { .. }));
+        assert_eq!(result.errors[1].line(), 4);
+    }
+
+    #[test]
+    fn test_validation_result_carries_document() {
+        let input = r#"Power
+  .core
+    - corrupts"#;
+
+        let result = validate(input);
+        assert_eq!(result.document, parse(input));
+    }
+
+    // ==================== Diagnostic code tests ====================
+
+    #[test]
+    fn test_every_variant_has_registered_code() {
+        let span = Span::default();
+        let s = String::new;
+        let all = [
+            ValidationError::InvalidIndentation { span, expected: "", found: 0 },
+            ValidationError::MissingFacetPrefix { span },
+            ValidationError::MissingClaimPrefix { span },
+            ValidationError::ConceptWithoutFacets { span, concept: s(), next: None },
+            ValidationError::FacetWithoutClaims { span, facet: s(), next: None },
+            ValidationError::OrphanFacet { span },
+            ValidationError::OrphanClaim { span },
+            ValidationError::EmptyClaimText { span },
+            ValidationError::UnexpectedIndentation { span, found: 0 },
+            ValidationError::EmptyConceptName { span },
+            ValidationError::EmptyFacetName { span },
+            ValidationError::InvalidReferenceFormat { span, reference: s() },
+            ValidationError::UndefinedReference { span, reference: s(), suggestions: vec![] },
+            ValidationError::EmptyCondition { span },
+            ValidationError::EmptySource { span },
+            ValidationError::EmptyReference { span },
+            ValidationError::BriefFormMissingLeftOperand { span, operator: s() },
+            ValidationError::BriefFormMissingRightOperand { span, operator: s() },
+            ValidationError::UnclosedEvolutionMarker { span },
+            ValidationError::EmptyEvolutionMarker { span },
+            ValidationError::MalformedEvolutionMarker { span },
+            ValidationError::StandaloneModifier { span, modifier: s() },
+            ValidationError::DuplicateConcept { span, concept: s(), first: span },
+            ValidationError::DuplicateFacet { span, facet: s(), first: span },
+            ValidationError::DuplicateClaim { span, first: span },
+            ValidationError::UnescapedMarker { span, marker: '&' },
+            ValidationError::MisplacedClaimElement { span, element: "", after: "", claim: span },
+            ValidationError::UnreferenceableConcept { span, concept: s() },
+            ValidationError::InvalidIdentifier { span, kind: "", name: s() },
+            ValidationError::GrammarMismatch { span, expected: s() },
+            ValidationError::CrossFileDuplicateConcept { span, concept: s(), first_file: s(), first: span },
+            ValidationError::LongClaim { span, words: 0, max: 0 },
+            ValidationError::MissingSource { span },
+            ValidationError::InvalidDirective { span, reason: "" },
+            ValidationError::UnknownLint { span, lint: s() },
+            ValidationError::ScriptRuleFailed { span, rule: s(), message: s() },
+            ValidationError::UnstableFormat { span },
+        ];
+
+        // `Custom` is left out: its codes belong to rules outside this crate
+        assert_eq!(all.len(), codes::CODES.len());
+        for error in &all {
+            let registered = codes::lookup(error.code()).expect("Expected registered code");
+            let debug = format!("{:?}", error);
+            assert!(debug.starts_with(registered.name), "{} is not {}", error.code(), debug);
+        }
+    }
+
+    #[test]
+    fn test_severity() {
+        let input = r#"Power
+  .core
+    - ^ corrupts &Trust.formation"#;
+
+        let result = validate(input);
+        let undefined = &result.errors[0];
+        assert_eq!(undefined.code(), "WVF0013");
+        assert_eq!(undefined.severity(), Severity::Error);
+        assert!(!undefined.is_warning());
+
+        let modifier = &result.warnings[0];
+        assert_eq!(modifier.code(), "WVF0022");
+        assert_eq!(modifier.severity(), Severity::Warning);
+        assert!(modifier.is_warning());
+        assert!(result.to_string().contains("[WVF0022] line 3: standalone modifier"));
+    }
+
+    // ==================== Span tests ====================
+
+    /// The source text a span covers
+    fn spanned(input: &str, span: Span) -> &str {
+        &input[span.start..span.end]
+    }
+
+    #[test]
+    fn test_claim_element_spans() {
+        let input = "Trust\n  .formation\n    - slow^ => collapse | over time @experience &Trust.formation [<= fast]";
+
+        let result = validate(input);
+        assert!(result.is_valid(), "Expected valid: {:?}", result.errors);
+        let claim = first_claim(&result);
+
+        assert_eq!(spanned(input, claim.text_span), "slow^ => collapse");
+        assert_eq!(claim.text_span.line, 3);
+        assert_eq!(claim.text_span.column, 7);
+
+        let cond = &claim.conditions[0];
+        assert_eq!(spanned(input, cond.span), "| over time");
+        assert_eq!(cond.span.column, 25);
+        assert_eq!(spanned(input, claim.sources[0].span), "@experience");
+        assert_eq!(spanned(input, claim.references[0].span), "&Trust.formation");
+
+        let bf = &claim.brief_forms[0];
+        assert_eq!(spanned(input, bf.span), "=>");
+        assert_eq!(bf.span.column, 13);
+        let m = &claim.modifiers[0];
+        assert_eq!(spanned(input, m.span), "^");
+        assert_eq!(m.span.column, 11);
+
+        let evo = claim.evolution.as_ref().unwrap();
+        assert_eq!(spanned(input, evo.span), "[<= fast]");
+    }
+
+    #[test]
+    fn test_error_spans() {
+        let input = "Power\r\n  .core\r\n    - corrupts &Trust.formaton\r\n   - bad indent\r\n    - => x";
+
+        let result = validate(input);
+        let undefined = result
+            .errors
+            .iter()
+            .find(|e| matches!(e, ValidationError::UndefinedReference { .. }))
+            .expect("Expected undefined reference");
+        let span = undefined.span();
+        assert_eq!(spanned(input, span), "&Trust.formaton");
+        assert_eq!((span.line, span.column), (3, 16));
+
+        let indent = result
+            .errors
+            .iter()
+            .find(|e| matches!(e, ValidationError::InvalidIndentation { .. }))
+            .expect("Expected invalid indentation");
+        assert_eq!(indent.span().len(), 3);
+        assert_eq!((indent.span().line, indent.span().column), (4, 1));
+
+        let missing = result
+            .errors
+            .iter()
+            .find(|e| matches!(e, ValidationError::BriefFormMissingLeftOperand { .. }))
+            .expect("Expected missing operand");
+        assert_eq!(spanned(input, missing.span()), "=>");
+        assert_eq!(missing.line(), 5);
+    }
+
+    #[test]
+    fn test_spans_count_columns_in_characters() {
+        let input = "Café\n  .menu\n    - crème brûlée => joy";
+
+        let result = validate(input);
+        let claim = first_claim(&result);
+        let bf = &claim.brief_forms[0];
+        assert_eq!(spanned(input, bf.span), "=>");
+        assert_eq!(bf.span.column, 20);
+    }
+
     // ==================== Inline element tests ====================
 
     #[test]
@@ -864,8 +1856,8 @@ Trust
         if let Some(line) = result.lines.iter().find(|l| matches!(l.line_type, LineType::Claim(_))) {
             if let LineType::Claim(claim) = &line.line_type {
                 assert_eq!(claim.text, "requires consistency");
-                assert!(claim.conditions.contains(&"over time".to_string()));
-                assert!(claim.sources.contains(&"personal-experience".to_string()));
+                assert!(claim.conditions.iter().any(|c| c.value == "over time"));
+                assert!(claim.sources.iter().any(|s| s.value == "personal-experience"));
             }
         }
     }
@@ -890,17 +1882,158 @@ Trust
             .collect();
         
         assert!(claims.len() >= 2);
-        assert!(claims[1].references.contains(&"Trust.formation".to_string()));
+        let reference = &claims[1].references[0].value;
+        assert_eq!(reference.concept, "Trust");
+        assert_eq!(reference.facet.as_deref(), Some("formation"));
+        assert_eq!(reference.to_string(), "Trust.formation");
+    }
+
+    #[test]
+    fn test_concept_reference() {
+        let input = "Trust\n  .formation\n    - slow\nPower\n  .core\n    - corrupts &Trust\n";
+
+        let result = validate(input);
+        assert!(result.is_valid(), "Expected valid: {:?}", result.errors);
+        let (_, _, claim) = result.document.claims().last().unwrap();
+        assert_eq!(
+            claim.data.references[0].value,
+            Reference {
+                concept: "Trust".to_string(),
+                facet: None
+            }
+        );
+        assert_eq!(spanned(input, claim.data.references[0].span), "&Trust");
+    }
+
+    #[test]
+    fn test_escaped_markers() {
+        let input = r"Market
+  .telecom
+    - AT\&T dominates | 1980s \@ peak";
+
+        let result = validate(input);
+        assert!(result.is_valid(), "Expected valid: {:?}", result.errors);
+        let claim = first_claim(&result);
+        assert_eq!(claim.text, r"AT\&T dominates");
+        assert_eq!(lexer::unescape(&claim.text), "AT&T dominates");
+        assert_eq!(claim.conditions[0].value, r"1980s \@ peak");
+        assert!(claim.references.is_empty() && claim.sources.is_empty());
+    }
+
+    #[test]
+    fn test_unescaped_markers() {
+        let input = "Market\n  .telecom\n    - AT&T dominates | 1980s\n    - contact via ops@corp\n    - x & y\n";
+
+        let result = validate(input);
+        let markers: Vec<_> = result
+            .errors
+            .iter()
+            .map(|e| match e {
+                ValidationError::UnescapedMarker { span, marker } => (*marker, spanned(input, *span)),
+                other => panic!("Unexpected error: {other:?}"),
+            })
+            .collect();
+        assert_eq!(markers, [('&', "&"), ('@', "@"), ('&', "&")]);
+        assert!(result.errors[0].to_string().contains("did you mean to escape it?"));
+
+        // The stray marker stays in the text instead of becoming an element
+        let claim = first_claim(&result);
+        assert_eq!(claim.text, "AT&T dominates");
+        assert_eq!(claim.conditions[0].value, "1980s");
+        assert!(claim.references.is_empty());
+    }
+
+    #[test]
+    fn test_misplaced_claim_elements() {
+        let input = "Power\n  .core\n    - corrupts @history | unchecked &Power | absolute\n    - rules | alone [<= shared] | often\n    - spreads @history slowly\n";
+
+        let result = validate(input);
+        let misplaced: Vec<_> = result
+            .errors
+            .iter()
+            .map(|e| match e {
+                ValidationError::MisplacedClaimElement { span, element, after, .. } => {
+                    (spanned(input, *span), *element, *after)
+                }
+                other => panic!("Unexpected error: {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            misplaced,
+            [
+                ("| unchecked", "conditions", "sources"),
+                ("| absolute", "conditions", "references"),
+                ("| often", "conditions", "evolution_marker"),
+                ("slowly", "claim_text", "sources"),
+            ]
+        );
+        assert_eq!(result.errors[0].message(), "conditions must come before sources");
+
+        // Stray text after an element is not a condition
+        let (_, _, claim) = result.document.claims().last().unwrap();
+        assert_eq!(claim.data.text, "spreads");
+        assert!(claim.data.conditions.is_empty());
+    }
+
+    #[test]
+    fn test_empty_inline_elements() {
+        let input = "Trust\n  .erosion\n    - fast | sudden @ &\n";
+
+        let errors = validate(input).errors;
+        assert!(matches!(errors[0], ValidationError::EmptySource { .. }), "{errors:?}");
+        assert!(matches!(errors[1], ValidationError::EmptyReference { .. }), "{errors:?}");
+    }
+
+    #[test]
+    fn test_undefined_concept_reference() {
+        let input = "Trust\n  .formation\n    - slow &Trsut &Power\n";
+
+        let result = validate(input);
+        assert_eq!(result.errors.len(), 2);
+        assert_eq!(
+            result.errors[0].message(),
+            "undefined reference 'Trsut' (no such concept in document; did you mean 'Trust'?)"
+        );
+        // Concept references are never corrected to facet references
+        assert!(matches!(&result.errors[1], ValidationError::UndefinedReference { suggestions, .. } if suggestions.is_empty()));
     }
 
     #[test]
     fn test_invalid_reference_format() {
         let input = r#"Power
   .core
-    - corrupts &InvalidReference"#;
+    - corrupts &Trust. &.formation"#;
         let result = validate(input);
         assert!(!result.is_valid());
-        assert!(result.errors.iter().any(|e| matches!(e, ValidationError::InvalidReferenceFormat { .. })));
+        assert_eq!(result.errors.len(), 2);
+        assert!(result.errors.iter().all(|e| matches!(e, ValidationError::InvalidReferenceFormat { .. })));
+    }
+
+    #[test]
+    fn test_names_must_be_identifiers() {
+        let input = "Human nature\n  .social life\n    - cooperative @field-notes @notes,\n    - see &Trust.core.deep &Power,\n";
+
+        let result = validate(input);
+        assert!(matches!(&result.warnings[..], [ValidationError::UnreferenceableConcept { concept, .. }] if concept == "Human nature"));
+        let invalid: Vec<_> = result
+            .errors
+            .iter()
+            .map(|e| match e {
+                ValidationError::InvalidIdentifier { kind, name, .. } => (*kind, name.as_str()),
+                ValidationError::InvalidReferenceFormat { reference, .. } => ("reference", reference.as_str()),
+                other => panic!("Unexpected error: {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            invalid,
+            [
+                ("facet", "social life"),
+                ("source", "notes,"),
+                ("reference", "Trust.core.deep"),
+                ("reference", "Power,"),
+            ]
+        );
+        assert!(is_identifier("Human-nature_2") && !is_identifier("Trust.core") && !is_identifier(""));
     }
 
     #[test]
@@ -913,6 +2046,35 @@ Trust
         assert!(result.errors.iter().any(|e| matches!(e, ValidationError::UndefinedReference { .. })));
     }
 
+    #[test]
+    fn test_undefined_reference_suggestions() {
+        let input = "Trust\n  .formation\n    - slow\n  .erosion\n    - fast &Trust.formaton &trust.formation &Trust.nothing\n";
+        let result = validate(input);
+
+        let suggestions: Vec<&[String]> = result
+            .errors
+            .iter()
+            .map(|e| match e {
+                ValidationError::UndefinedReference { suggestions, .. } => suggestions.as_slice(),
+                _ => panic!("Unexpected error: {}", e),
+            })
+            .collect();
+        assert_eq!(suggestions, [&["Trust.formation".to_string()][..], &["Trust.formation".to_string()], &[]]);
+        assert_eq!(
+            result.errors[0].message(),
+            "undefined reference 'Trust.formaton' (no such concept.facet in document; did you mean 'Trust.formation'?)"
+        );
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("formation", "formation"), 0);
+        assert_eq!(edit_distance("formaton", "formation"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("trsut", "trust"), 1);
+    }
+
     #[test]
     fn test_valid_cross_reference() {
         // References between different concepts should work
@@ -1005,6 +2167,28 @@ Trust
         assert!(result.errors.iter().any(|e| matches!(e, ValidationError::BriefFormMissingRightOperand { .. })));
     }
 
+    #[test]
+    fn test_brief_forms_respect_word_boundaries() {
+        let input = "Art\n  .media\n    - canvas is obvious, versus-like | vs @canvas\n";
+
+        let result = validate(input);
+        assert!(result.is_valid(), "Expected valid: {:?}", result.errors);
+        assert!(first_claim(&result).brief_forms.is_empty());
+    }
+
+    #[test]
+    fn test_attached_brief_form() {
+        let input = "Power\n  .nature\n    - power=>corruption^ | x<=y\n";
+
+        let result = validate(input);
+        assert!(result.is_valid(), "Expected valid: {:?}", result.errors);
+        let claim = first_claim(&result);
+        assert_eq!(claim.brief_forms.len(), 1);
+        assert_eq!(claim.brief_forms[0].left_operand, "power");
+        assert_eq!(claim.brief_forms[0].right_operand, "corruption");
+        assert_eq!(spanned(input, claim.brief_forms[0].span), "=>");
+    }
+
     // ==================== Modifier tests ====================
 
     #[test]
@@ -1121,6 +2305,17 @@ Trust
         assert!(!result.has_warnings(), "Expected no warnings: {:?}", result.warnings);
     }
 
+    #[test]
+    fn test_modifiers_need_a_term() {
+        let input = "Power\n  .core\n    - fast!? => ?! growth\n";
+
+        let result = validate(input);
+        let claim = first_claim(&result);
+        let attached: Vec<(char, &str)> = claim.modifiers.iter().map(|m| (m.symbol, m.attached_to.as_str())).collect();
+        assert_eq!(attached, [('!', "fast"), ('?', "fast"), ('?', ""), ('!', "")]);
+        assert_eq!(result.warnings.len(), 2, "{:?}", result.warnings);
+    }
+
     // ==================== Evolution marker tests ====================
 
     #[test]
@@ -1157,6 +2352,17 @@ Trust
         }
     }
 
+    #[test]
+    fn test_escaped_evolution_marker_is_text() {
+        let input = r"Math
+  .notation
+    - \[<= reads as at most";
+
+        let result = validate(input);
+        assert!(result.is_valid(), "Expected valid: {:?}", result.errors);
+        assert!(first_claim(&result).evolution.is_none());
+    }
+
     #[test]
     fn test_unclosed_evolution_marker() {
         let input = r#"Human-nature
@@ -1179,6 +2385,44 @@ Trust
         assert!(result.errors.iter().any(|e| matches!(e, ValidationError::EmptyEvolutionMarker { .. })));
     }
 
+    // ==================== Duplicate tests ====================
+
+    #[test]
+    fn test_duplicate_concept() {
+        let input = "Trust\n  .formation\n    - slow\n\nTrust\n  .erosion\n    - fast\n";
+        let result = validate(input);
+        assert_eq!(result.errors.len(), 1);
+        let ValidationError::DuplicateConcept { span, first, .. } = &result.errors[0] else {
+            panic!("Expected duplicate concept: {:?}", result.errors);
+        };
+        assert_eq!((span.line, first.line), (5, 1));
+        assert_eq!(result.errors[0].labels()[0].message, "first declared here");
+    }
+
+    #[test]
+    fn test_duplicate_facet() {
+        let input = "Trust\n  .formation\n    - slow\n  .formation\n    - contextual\nPower\n  .formation\n    - top-down\n";
+        let result = validate(input);
+        assert_eq!(result.errors.len(), 1, "{:?}", result.errors);
+        let ValidationError::DuplicateFacet { span, first, facet } = &result.errors[0] else {
+            panic!("Expected duplicate facet: {:?}", result.errors);
+        };
+        assert_eq!(facet, "formation");
+        assert_eq!((span.line, first.line), (4, 2));
+    }
+
+    #[test]
+    fn test_duplicate_claim_after_whitespace_normalization() {
+        let input = "Trust\n  .formation\n    - slow | early\n    - slow  |   early\n    - slow\n  .erosion\n    - slow\n";
+        let result = validate(input);
+        assert!(result.is_valid());
+        assert_eq!(result.warnings.len(), 1, "{:?}", result.warnings);
+        let ValidationError::DuplicateClaim { span, first } = &result.warnings[0] else {
+            panic!("Expected duplicate claim: {:?}", result.warnings);
+        };
+        assert_eq!((span.line, first.line), (4, 3));
+    }
+
     // ==================== Full document tests ====================
 
     #[test]
@@ -1236,4 +2480,30 @@ Institutions
         let result = validate(input);
         assert!(result.is_valid(), "Expected valid document: {:?}", result.errors);
     }
+
+    // ==================== Serde tests ====================
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_document_round_trips_through_json() {
+        let input = "Power\n  .core\n    - concentration^ => abuse^ | unchecked @history &Trust [<= neutral]\n";
+        let document = parse(input);
+        let json = serde_json::to_string(&document).unwrap();
+        assert_eq!(serde_json::from_str::<Document>(&json).unwrap(), document);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_validation_result_serializes_diagnostics() {
+        let result = validate("Power\n  .core\n    - corrupts &Missing\n");
+        let value = serde_json::to_value(&result).unwrap();
+        assert!(value.get("lines").is_none());
+        assert_eq!(value["document"]["concepts"][0]["name"], "Power");
+
+        let error = &value["errors"][0];
+        assert_eq!(error["code"], result.errors[0].code());
+        assert_eq!(error["severity"], "error");
+        assert_eq!(error["span"]["line"], 3);
+        assert!(error["message"].as_str().unwrap().starts_with("undefined reference"));
+    }
 }
@@ -0,0 +1,237 @@
+//! Lints: checks whose level each project chooses
+//!
+//! Structural and syntax errors always make a document invalid. Lints are
+//! softer: each has a name such as `long-claim`, a stable diagnostic code and a
+//! default [`Level`], and a [`LintConfig`] can allow, warn about or deny it.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// What to do with a lint's diagnostics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// Don't check
+    Allow,
+    /// Report as a warning
+    Warn,
+    /// Report as an error, making the document invalid
+    Deny,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Level::Allow => "allow",
+            Level::Warn => "warn",
+            Level::Deny => "deny",
+        };
+        f.pad(name)
+    }
+}
+
+impl FromStr for Level {
+    type Err = LintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(Level::Allow),
+            "warn" => Ok(Level::Warn),
+            "deny" => Ok(Level::Deny),
+            _ => Err(LintError::InvalidLevel(s.to_string())),
+        }
+    }
+}
+
+/// Errors in a lint configuration
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum LintError {
+    #[error("unknown lint '{0}'")]
+    UnknownLint(String),
+
+    #[error("invalid lint level '{0}' (expected allow, warn or deny)")]
+    InvalidLevel(String),
+}
+
+/// A registered lint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lint {
+    /// Name used in configuration, e.g. `long-claim`
+    pub name: &'static str,
+    /// The diagnostic code it reports (see [`codes`](crate::codes))
+    pub code: &'static str,
+    /// Level when nothing configures it
+    pub default: Level,
+}
+
+/// Every lint, in code order
+pub const LINTS: &[Lint] = &[
+    Lint { name: "standalone-modifier", code: "WVF0022", default: Level::Warn },
+    Lint { name: "unreferenceable-concept", code: "WVF0028", default: Level::Warn },
+    Lint { name: "long-claim", code: "WVF0032", default: Level::Allow },
+    Lint { name: "missing-source", code: "WVF0033", default: Level::Allow },
+];
+
+/// Look up a lint by name (`long-claim`) or by diagnostic code (`WVF0032`, `wvf32`)
+pub fn lookup(lint: &str) -> Option<&'static Lint> {
+    LINTS.iter().find(|l| l.name == lint).or_else(|| {
+        let code = crate::codes::lookup(lint)?;
+        LINTS.iter().find(|l| l.code == code.code)
+    })
+}
+
+/// Default for [`LintConfig::max_claim_words`]
+pub const DEFAULT_MAX_CLAIM_WORDS: usize = 12;
+
+/// Lint levels and options
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintConfig {
+    levels: HashMap<String, Level>,
+    /// Report every warning as an error
+    pub deny_warnings: bool,
+    /// Most words a claim's text may have before `long-claim` reports it
+    pub max_claim_words: usize,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        LintConfig {
+            levels: HashMap::new(),
+            deny_warnings: false,
+            max_claim_words: DEFAULT_MAX_CLAIM_WORDS,
+        }
+    }
+}
+
+impl LintConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the level of a lint, by name or code
+    ///
+    /// Built-in lints may be named by code; any other name is taken to be a
+    /// [`Rule`](crate::rule::Rule) of your own, checked by
+    /// [`Validator::check_lints`](crate::Validator::check_lints).
+    pub fn set_level(&mut self, lint: &str, level: Level) -> Result<(), LintError> {
+        let name = match lookup(lint) {
+            Some(lint) => lint.name,
+            None if lint.is_empty() || crate::codes::lookup(lint).is_some() => {
+                return Err(LintError::UnknownLint(lint.to_string()));
+            }
+            None => lint,
+        };
+        self.levels.insert(name.to_string(), level);
+        Ok(())
+    }
+
+    /// The configured level of a lint, ignoring `deny_warnings`
+    pub fn level(&self, name: &str, default: Level) -> Level {
+        self.levels.get(name).copied().unwrap_or(default)
+    }
+
+    /// The names of every lint given a level, in sorted order
+    pub fn configured(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.levels.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ValidationError, Validator, validate};
+
+    const SOURCE: &str = "Power\n  .core\n    - ^ concentrates in the hands of the few over a long enough stretch of time\n";
+
+    #[test]
+    fn test_every_lint_has_a_warning_code() {
+        for lint in LINTS {
+            let code = crate::codes::lookup(lint.code).expect("Expected a registered code");
+            assert!(code.severity < crate::Severity::Error, "{} is an error", lint.code);
+        }
+    }
+
+    #[test]
+    fn test_lookup_by_name_or_code() {
+        assert_eq!(lookup("long-claim").unwrap().code, "WVF0032");
+        assert_eq!(lookup("wvf22").unwrap().name, "standalone-modifier");
+        assert!(lookup("WVF0001").is_none());
+        assert!(lookup("no-such-lint").is_none());
+    }
+
+    #[test]
+    fn test_default_levels() {
+        let result = validate(SOURCE);
+        assert!(result.is_valid());
+        assert!(matches!(&result.warnings[..], [ValidationError::StandaloneModifier { .. }]));
+    }
+
+    #[test]
+    fn test_configured_levels() {
+        let mut config = LintConfig::new();
+        config.set_level("standalone-modifier", Level::Allow).unwrap();
+        config.set_level("long-claim", Level::Warn).unwrap();
+        config.set_level("WVF0033", Level::Deny).unwrap();
+
+        let result = Validator::new().lints(config.clone()).validate(SOURCE);
+        assert!(matches!(&result.errors[..], [ValidationError::MissingSource { .. }]));
+        assert!(matches!(&result.warnings[..], [ValidationError::LongClaim { words: 14, max: 12, .. }]));
+
+        config.max_claim_words = 14;
+        assert!(Validator::new().lints(config).validate(SOURCE).warnings.is_empty());
+    }
+
+    #[test]
+    fn test_deny_warnings() {
+        let config = LintConfig { deny_warnings: true, ..LintConfig::new() };
+        let result = Validator::new().lints(config).validate(SOURCE);
+        assert!(result.warnings.is_empty());
+        assert!(matches!(&result.errors[..], [ValidationError::StandaloneModifier { .. }]));
+        assert_eq!(result.diagnostics()[0].0, crate::Severity::Error);
+    }
+
+    #[test]
+    fn test_allow_directive() {
+        let mut config = LintConfig::new();
+        config.set_level("missing-source", Level::Deny).unwrap();
+        let validator = Validator::new().lints(config);
+        assert!(!validator.validate(SOURCE).is_valid());
+
+        let source = format!("#![allow(missing-source, WVF0022, long-claims)]\n\n{}", SOURCE);
+        let result = validator.validate(&source);
+        assert!(result.errors.is_empty());
+        assert!(matches!(&result.warnings[..], [ValidationError::UnknownLint { lint, .. }] if lint == "long-claims"));
+        assert_eq!(result.document.allow.len(), 3);
+    }
+
+    #[test]
+    fn test_misplaced_directive() {
+        let source = format!("{}#![allow(standalone-modifier)]\n", SOURCE);
+        let result = validate(&source);
+        assert!(matches!(&result.errors[..], [ValidationError::InvalidDirective { span, .. }] if span.line == 4));
+        assert!(matches!(&result.warnings[..], [ValidationError::StandaloneModifier { .. }]));
+
+        let result = validate("#![allow(long-claim\n\nA\n  .b\n    - c\n");
+        assert!(matches!(&result.errors[..], [ValidationError::InvalidDirective { .. }]));
+    }
+
+    #[test]
+    fn test_invalid_configuration() {
+        let mut config = LintConfig::new();
+        assert_eq!(
+            config.set_level("WVF0001", Level::Allow),
+            Err(LintError::UnknownLint("WVF0001".to_string()))
+        );
+        // Other names may belong to rules of the caller's own
+        config.set_level("long-claims", Level::Deny).unwrap();
+        assert_eq!(config.configured(), ["long-claims"]);
+        assert_eq!(
+            Validator::new().lints(config).check_lints(),
+            Err(LintError::UnknownLint("long-claims".to_string()))
+        );
+        assert_eq!("forbid".parse::<Level>(), Err(LintError::InvalidLevel("forbid".to_string())));
+    }
+}
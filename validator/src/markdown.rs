@@ -0,0 +1,470 @@
+//! Markdown export and import
+//!
+//! [`to_markdown`] renders a document for readers who do not know the notation:
+//! concepts become `#` headings, facets `##` headings and claims bullets, with
+//! the inline elements spelled out in an annotation after the claim text:
+//!
+//! ```markdown
+//! # Power
+//!
+//! ## nature
+//!
+//! - corrupts _(when unchecked · source: history · see [Trust.erosion](#trust))_
+//! - adaptive _(previously: inherently good)_
+//! ```
+//!
+//! A `·` or `&` inside a condition or prior belief is written as an HTML entity
+//! (`&middot;`, `&amp;`), so it renders the same but is not read as a separator.
+//!
+//! [`from_markdown`] reads that form back, and also nested bullet outlines
+//! (concept, then facet, then claim) with or without headings. Anything it
+//! cannot place in the tree is reported as [`Unmapped`] rather than guessed at.
+
+use crate::lexer::{escape, unescape};
+use crate::{Claim, ClaimData, Concept, Document, EvolutionMarker, Facet, Reference, Span, Spanned};
+use thiserror::Error;
+
+/// Characters escaped in Markdown text so they are not read as formatting
+const MARKDOWN_SPECIAL: &[char] = &['\\', '*', '_', '`', '[', ']', '<'];
+
+/// Separates the parts of a claim annotation
+const SEPARATOR: &str = " · ";
+
+/// A line of Markdown that has no place in a Worldview document
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum Unmapped {
+    #[error("line {}: text outside a list", .span.line)]
+    Text { span: Span },
+
+    #[error("line {}: code block", .span.line)]
+    CodeBlock { span: Span },
+
+    #[error("line {}: level {level} heading (only # concepts and ## facets map to a worldview)", .span.line)]
+    Heading { span: Span, level: usize },
+
+    #[error("line {}: item nested below claim level", .span.line)]
+    TooDeep { span: Span },
+
+    #[error("line {}: item under a line that could not be mapped", .span.line)]
+    UnderUnmapped { span: Span },
+
+    #[error("line {}: facet heading before any concept", .span.line)]
+    OrphanFacet { span: Span },
+
+    #[error("line {}: annotation '{part}' is not a condition, source, reference or prior belief", .span.line)]
+    Annotation { span: Span, part: String },
+}
+
+impl Unmapped {
+    /// Location of the line in the Markdown source
+    pub fn span(&self) -> Span {
+        match self {
+            Unmapped::Text { span }
+            | Unmapped::CodeBlock { span }
+            | Unmapped::Heading { span, .. }
+            | Unmapped::TooDeep { span }
+            | Unmapped::UnderUnmapped { span }
+            | Unmapped::OrphanFacet { span }
+            | Unmapped::Annotation { span, .. } => *span,
+        }
+    }
+}
+
+/// Render a document as Markdown
+pub fn to_markdown(document: &Document) -> String {
+    let mut output = String::new();
+    for concept in &document.concepts {
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        output.push_str(&format!("# {}\n", markdown_escape(&concept.name)));
+        for facet in &concept.facets {
+            output.push_str(&format!("\n## {}\n\n", markdown_escape(&facet.name)));
+            for claim in &facet.claims {
+                output.push_str(&format!("- {}\n", claim_markdown(&claim.data)));
+            }
+        }
+    }
+    output
+}
+
+/// A claim's text followed by its inline elements in words
+fn claim_markdown(claim: &ClaimData) -> String {
+    let text = markdown_escape(&unescape(&claim.text));
+    let mut parts = Vec::new();
+    for condition in &claim.conditions {
+        parts.push(format!("when {}", annotation_escape(&markdown_escape(&unescape(condition)))));
+    }
+    for source in &claim.sources {
+        parts.push(format!("source: {}", markdown_escape(source)));
+    }
+    for reference in &claim.references {
+        let target = markdown_escape(&reference.to_string());
+        parts.push(format!("see [{}](#{})", target, anchor(&reference.concept)));
+    }
+    if let Some(evolution) = &claim.evolution {
+        let prior = annotation_escape(&markdown_escape(&unescape(&evolution.prior_belief)));
+        parts.push(format!("previously: {}", prior));
+    }
+    if parts.is_empty() { text } else { format!("{} _({})_", text, parts.join(SEPARATOR)) }
+}
+
+/// The anchor of a heading as GitHub renders it, e.g. `human-nature` for `Human-nature`
+fn anchor(heading: &str) -> String {
+    heading
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c.to_ascii_lowercase()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Backslash-escape the characters Markdown reads as formatting
+fn markdown_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if MARKDOWN_SPECIAL.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Write the separator's `·` in annotation text as an entity, so the text is not
+/// split there on import, and `&` too, so an entity written as text stays text
+fn annotation_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('·', "&middot;")
+}
+
+/// Resolve the entities [`annotation_escape`] writes
+fn annotation_unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("&middot;") {
+            unescaped.push('·');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("&amp;") {
+            unescaped.push('&');
+            rest = after;
+        } else {
+            unescaped.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    unescaped
+}
+
+/// Resolve Markdown backslash escapes of ASCII punctuation
+fn markdown_unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.next_if(|next| c == '\\' && next.is_ascii_punctuation()) {
+            Some(escaped) => unescaped.push(escaped),
+            None => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Read a Markdown outline into a document, with every line that could not be mapped
+///
+/// The document has no spans; render it with [`crate::format::format_tree`].
+pub fn from_markdown(markdown: &str) -> (Document, Vec<Unmapped>) {
+    let mut reader = Reader::default();
+    let mut offset = 0;
+    let mut in_code_block = false;
+    for (index, raw) in markdown.split('\n').enumerate() {
+        let line = raw.strip_suffix('\r').unwrap_or(raw);
+        let content = line.trim();
+        let indent = line.len() - line.trim_start().len();
+        let span = Span {
+            line: index + 1,
+            column: line[..indent].chars().count() + 1,
+            start: offset + indent,
+            end: offset + indent + content.len(),
+        };
+        offset += raw.len() + 1;
+
+        if content.starts_with("```") || content.starts_with("~~~") {
+            if !in_code_block {
+                reader.unmapped.push(Unmapped::CodeBlock { span });
+            }
+            in_code_block = !in_code_block;
+        } else if in_code_block || content.is_empty() {
+            continue;
+        } else if let Some((level, text)) = heading(content) {
+            reader.heading(level, text, span);
+        } else if let Some(text) = bullet(content) {
+            reader.item(line[..indent].replace('\t', "    ").len(), text, span);
+        } else {
+            reader.unmapped.push(Unmapped::Text { span });
+        }
+    }
+    (reader.document, reader.unmapped)
+}
+
+/// `(level, text)` of an ATX heading such as `## nature`
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[level..];
+    if level == 0 || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    // A closing sequence of '#' is dropped only when separated by a space, as in `## nature ##`
+    let text = rest.trim();
+    let closed = text.trim_end_matches('#');
+    let text = if closed.is_empty() || closed.ends_with([' ', '\t']) { closed.trim_end() } else { text };
+    Some((level, text))
+}
+
+/// The text of a list item: `- text`, `* text`, `+ text` or `1. text`
+fn bullet(line: &str) -> Option<&str> {
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let rest = if digits > 0 {
+        line[digits..].strip_prefix(['.', ')'])?
+    } else {
+        line.strip_prefix(['-', '*', '+'])?
+    };
+    if rest.is_empty() || rest.starts_with([' ', '\t']) { Some(rest.trim()) } else { None }
+}
+
+/// Outline state while reading Markdown
+#[derive(Default)]
+struct Reader {
+    document: Document,
+    unmapped: Vec<Unmapped>,
+    /// Tree level of the items under the current heading: 0 at the top, 1 under
+    /// a concept heading, 2 under a facet heading
+    base: usize,
+    /// Whether the current heading could not be mapped
+    heading_unmapped: bool,
+    /// Indentation of each open list item, and whether it was mapped
+    items: Vec<(usize, bool)>,
+}
+
+impl Reader {
+    fn heading(&mut self, level: usize, text: &str, span: Span) {
+        self.items.clear();
+        self.heading_unmapped = false;
+        match level {
+            1 => {
+                self.concept(text);
+                self.base = 1;
+            }
+            2 if !self.document.concepts.is_empty() => {
+                self.facet(text);
+                self.base = 2;
+            }
+            2 => {
+                self.unmapped.push(Unmapped::OrphanFacet { span });
+                self.heading_unmapped = true;
+            }
+            _ => {
+                self.unmapped.push(Unmapped::Heading { span, level });
+                self.heading_unmapped = true;
+            }
+        }
+    }
+
+    fn item(&mut self, indent: usize, text: &str, span: Span) {
+        while self.items.last().is_some_and(|&(open, _)| open >= indent) {
+            self.items.pop();
+        }
+        let parent_unmapped = match self.items.last() {
+            Some(&(_, mapped)) => !mapped,
+            None => self.heading_unmapped,
+        };
+        let mapped = if parent_unmapped {
+            self.unmapped.push(Unmapped::UnderUnmapped { span });
+            false
+        } else {
+            match self.base + self.items.len() {
+                0 => {
+                    self.concept(text);
+                    true
+                }
+                1 => {
+                    self.facet(text);
+                    true
+                }
+                2 => self.claim(text, span),
+                _ => {
+                    self.unmapped.push(Unmapped::TooDeep { span });
+                    false
+                }
+            }
+        };
+        self.items.push((indent, mapped));
+    }
+
+    fn concept(&mut self, text: &str) {
+        self.document.concepts.push(Concept {
+            name: markdown_unescape(text),
+            ..Concept::default()
+        });
+    }
+
+    fn facet(&mut self, text: &str) {
+        let name = markdown_unescape(text);
+        let concept = self.document.concepts.last_mut().expect("facets follow a concept");
+        concept.facets.push(Facet {
+            name: name.strip_prefix('.').map(str::to_string).unwrap_or(name),
+            ..Facet::default()
+        });
+    }
+
+    fn claim(&mut self, text: &str, span: Span) -> bool {
+        let data = match claim_data(text) {
+            Ok(data) => data,
+            Err(part) => {
+                self.unmapped.push(Unmapped::Annotation { span, part });
+                return false;
+            }
+        };
+        let concept = self.document.concepts.last_mut().expect("claims follow a concept");
+        let facet = concept.facets.last_mut().expect("claims follow a facet");
+        facet.claims.push(Claim { span: Span::default(), data });
+        true
+    }
+}
+
+/// A claim from bullet text and its annotation, or the annotation part that cannot be read
+fn claim_data(text: &str) -> Result<ClaimData, String> {
+    let (text, annotation) = match text.strip_suffix(")_").and_then(|t| t.rsplit_once(" _(")) {
+        Some((text, annotation)) => (text, Some(annotation)),
+        None => (text, None),
+    };
+    let mut data = ClaimData {
+        text: escape(&markdown_unescape(text)).into_owned(),
+        ..ClaimData::default()
+    };
+    for part in annotation.into_iter().flat_map(|a| a.split(SEPARATOR)) {
+        let spanned = |value: &str| Spanned {
+            value: escape(&markdown_unescape(value)).into_owned(),
+            span: Span::default(),
+        };
+        if let Some(condition) = part.strip_prefix("when ") {
+            data.conditions.push(spanned(&annotation_unescape(condition)));
+        } else if let Some(source) = part.strip_prefix("source: ") {
+            data.sources.push(spanned(source));
+        } else if let Some(target) = part.strip_prefix("see ") {
+            // `[Concept.facet](#anchor)` as exported, or a bare target
+            let target = target
+                .strip_prefix('[')
+                .and_then(|t| t.split_once("]("))
+                .filter(|(_, link)| link.ends_with(')'))
+                .map_or(target, |(label, _)| label);
+            data.references.push(Spanned {
+                value: Reference::parse(&markdown_unescape(target)),
+                span: Span::default(),
+            });
+        } else if let Some(prior) = part.strip_prefix("previously: ").filter(|_| data.evolution.is_none()) {
+            data.evolution = Some(EvolutionMarker {
+                prior_belief: spanned(&annotation_unescape(prior)).value,
+                span: Span::default(),
+            });
+        } else {
+            return Err(part.to_string());
+        }
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{format_source, format_tree};
+    use crate::parse;
+
+    #[test]
+    fn test_to_markdown() {
+        let document = parse(
+            "Power\n  .nature\n    - corrupts | unchecked @history &Trust.erosion\n    - adaptive* [<= inherently good]\n\nTrust\n  .erosion\n    - AT\\&T fast\n",
+        );
+        assert_eq!(
+            to_markdown(&document),
+            "# Power\n\n## nature\n\n\
+             - corrupts _(when unchecked · source: history · see [Trust.erosion](#trust))_\n\
+             - adaptive\\* _(previously: inherently good)_\n\n\
+             # Trust\n\n## erosion\n\n- AT&T fast\n"
+        );
+    }
+
+    #[test]
+    fn test_markdown_round_trip() {
+        let example = include_str!("../../example.wvf");
+        let (document, unmapped) = from_markdown(&to_markdown(&parse(example)));
+        assert_eq!(unmapped, []);
+        assert_eq!(format_source(&format_tree(&document)).unwrap(), example);
+    }
+
+    #[test]
+    fn test_separator_round_trip() {
+        let source = "Trade\n  .terms\n    - a · b | c · d | R\\&amp;D [<= e · f]\n";
+        let markdown = to_markdown(&parse(source));
+        assert_eq!(
+            markdown,
+            "# Trade\n\n## terms\n\n- a · b _(when c &middot; d · when R&amp;amp;D · previously: e &middot; f)_\n"
+        );
+        let (document, unmapped) = from_markdown(&markdown);
+        assert_eq!(unmapped, []);
+        assert_eq!(format_source(&format_tree(&document)).unwrap(), source);
+    }
+
+    #[test]
+    fn test_import_bullet_outline() {
+        let notes = "* Power\n  * nature\n    * corrupts\n    * AT&T | 1980s\n* Trust\n\t- formation\n\t\t- slow\n";
+        let (document, unmapped) = from_markdown(notes);
+        assert_eq!(unmapped, []);
+        assert_eq!(
+            format_source(&format_tree(&document)).unwrap(),
+            "Power\n  .nature\n    - corrupts\n    - AT\\&T \\| 1980s\n\nTrust\n  .formation\n    - slow\n"
+        );
+    }
+
+    #[test]
+    fn test_import_reports_unmapped_lines() {
+        let notes = "Some notes\n\n# Power\n\n### Details\n- under it\n\n## nature\n\n- corrupts\n  - why\n    - deeper\n- odd _(maybe: later)_\n\n```\ncode\n```\n";
+        let (document, unmapped) = from_markdown(notes);
+        let lines: Vec<(usize, &str)> = unmapped
+            .iter()
+            .map(|u| {
+                let kind = match u {
+                    Unmapped::Text { .. } => "text",
+                    Unmapped::Heading { .. } => "heading",
+                    Unmapped::UnderUnmapped { .. } => "under",
+                    Unmapped::TooDeep { .. } => "deep",
+                    Unmapped::Annotation { .. } => "annotation",
+                    Unmapped::CodeBlock { .. } => "code",
+                    Unmapped::OrphanFacet { .. } => "orphan",
+                };
+                (u.span().line, kind)
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            [(1, "text"), (5, "heading"), (6, "under"), (11, "deep"), (12, "under"), (13, "annotation"), (15, "code")]
+        );
+        assert_eq!(unmapped[5].to_string(), "line 13: annotation 'maybe: later' is not a condition, source, reference or prior belief");
+
+        let claims: Vec<_> = document.claims().map(|(_, _, c)| c.data.text.as_str()).collect();
+        assert_eq!(claims, ["corrupts"]);
+    }
+
+    #[test]
+    fn test_import_orphans() {
+        let (_, unmapped) = from_markdown("## nature\n- corrupts\n");
+        assert!(matches!(unmapped[..], [Unmapped::OrphanFacet { .. }, Unmapped::UnderUnmapped { .. }]));
+
+        let (document, unmapped) = from_markdown("# Power\n- nature\n## core ##\n- a\n# C#\n\n");
+        assert_eq!(unmapped, []);
+        let facets: Vec<_> = document.concepts[0].facets.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(facets, ["nature", "core"]);
+        assert_eq!(document.concepts[1].name, "C#");
+    }
+}
@@ -0,0 +1,254 @@
+//! Compiler-style rendering of diagnostics against their source text
+//!
+//! ```text
+//! error[WVF0013]: undefined reference 'Trust.formaton' (no such concept.facet in document)
+//!  --> example.wvf:3:16
+//!   |
+//! 3 |     - corrupts &Trust.formaton
+//!   |                ^^^^^^^^^^^^^^^
+//! ```
+
+use crate::{Label, Severity, Span, ValidationError, ValidationResult};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const GUTTER: &str = "\x1b[1;34m";
+
+/// Renders diagnostics with the offending source lines underlined
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Renderer {
+    color: bool,
+}
+
+impl Renderer {
+    /// A renderer producing plain text
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable or disable ANSI colour output
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Render every diagnostic in `result`, in source order, followed by a summary line
+    pub fn render(&self, result: &ValidationResult, source: &str, path: &str) -> String {
+        let mut output = String::new();
+        for (severity, diagnostic) in result.diagnostics() {
+            output.push_str(&self.render_at(severity, diagnostic, source, path));
+            output.push('\n');
+        }
+        output.push_str(&self.render_summary(result, path));
+        output
+    }
+
+    /// Render a single diagnostic with its source context
+    pub fn render_diagnostic(&self, diagnostic: &ValidationError, source: &str, path: &str) -> String {
+        self.render_at(diagnostic.severity(), diagnostic, source, path)
+    }
+
+    /// Render a diagnostic as reported at `severity`, which lint levels may raise
+    fn render_at(&self, severity: Severity, diagnostic: &ValidationError, source: &str, path: &str) -> String {
+        let span = diagnostic.span();
+
+        // Primary label first, then secondary labels, ordered by line
+        let mut labels: Vec<(Span, String, bool)> = vec![(span, String::new(), true)];
+        labels.extend(
+            diagnostic
+                .labels()
+                .into_iter()
+                .map(|Label { span, message }| (span, message, false)),
+        );
+        labels.sort_by_key(|(span, _, primary)| (span.line, !primary));
+
+        let width = labels.iter().map(|(s, _, _)| s.line).max().unwrap_or(1).to_string().len();
+        let pad = " ".repeat(width);
+
+        let mut out = format!(
+            "{}{}\n",
+            self.paint(severity_style(severity), &format!("{}[{}]", severity, diagnostic.code())),
+            self.paint(BOLD, &format!(": {}", diagnostic.message())),
+        );
+        out.push_str(&format!(
+            "{}{} {}:{}:{}\n",
+            pad,
+            self.paint(GUTTER, "-->"),
+            path,
+            span.line,
+            span.column
+        ));
+        out.push_str(&format!("{} {}\n", pad, self.paint(GUTTER, "|")));
+
+        let mut previous_line: Option<usize> = None;
+        for (label_span, message, primary) in &labels {
+            let (line_start, text) = line_at(source, *label_span);
+            if previous_line != Some(label_span.line) {
+                if previous_line.is_some_and(|prev| label_span.line > prev + 1) {
+                    out.push_str(&format!("{}\n", self.paint(GUTTER, "...")));
+                }
+                out.push_str(&format!(
+                    "{} {}\n",
+                    self.paint(GUTTER, &format!("{:>width$} |", label_span.line)),
+                    text
+                ));
+            }
+            previous_line = Some(label_span.line);
+
+            let start = label_span.start.clamp(line_start, line_start + text.len());
+            let end = label_span.end.clamp(start, line_start + text.len());
+            let offset = source[line_start..start].chars().count();
+            let length = source[start..end].chars().count().max(1);
+            let (marker, style) = if *primary {
+                ("^", severity_style(severity))
+            } else {
+                ("-", GUTTER)
+            };
+            let mut underline = marker.repeat(length);
+            if !message.is_empty() {
+                underline.push(' ');
+                underline.push_str(message);
+            }
+            out.push_str(&format!(
+                "{} {} {}{}\n",
+                pad,
+                self.paint(GUTTER, "|"),
+                " ".repeat(offset),
+                self.paint(style, &underline)
+            ));
+        }
+
+        if let Some(fix) = diagnostic.fix(source) {
+            out.push_str(&format!("{} {} help: {}\n", pad, self.paint(GUTTER, "="), fix.message));
+        }
+
+        out
+    }
+
+    /// Render the per-file summary line, e.g. `example.wvf: 2 errors, 1 warning`
+    pub fn render_summary(&self, result: &ValidationResult, path: &str) -> String {
+        let errors = plural(result.errors.len(), "error");
+        let warnings = plural(result.warnings.len(), "warning");
+        let summary = match (result.errors.len(), result.warnings.len()) {
+            (0, 0) => "valid".to_string(),
+            (0, _) => format!("valid, {}", warnings),
+            (_, 0) => errors,
+            _ => format!("{}, {}", errors, warnings),
+        };
+        format!("{}\n", self.paint(BOLD, &format!("{}: {}", path, summary)))
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// ANSI style for a severity
+fn severity_style(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "\x1b[1;31m",
+        Severity::Warning => "\x1b[1;33m",
+        Severity::Info => "\x1b[1;36m",
+        Severity::Hint => "\x1b[1;32m",
+    }
+}
+
+/// The line containing `span`: its byte offset and text (without line ending)
+fn line_at(source: &str, span: Span) -> (usize, &str) {
+    let position = span.start.min(source.len());
+    let start = source[..position].rfind('\n').map_or(0, |i| i + 1);
+    let end = source[start..].find('\n').map_or(source.len(), |i| start + i);
+    let text = &source[start..end];
+    (start, text.strip_suffix('\r').unwrap_or(text))
+}
+
+/// "1 error", "2 errors"
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate;
+
+    #[test]
+    fn test_render_primary_span() {
+        let source = "Power\n  .core\n    - corrupts &Trust.formaton\n";
+        let result = validate(source);
+        let rendered = Renderer::new().render_diagnostic(&result.errors[0], source, "power.wvf");
+
+        assert_eq!(
+            rendered,
+            "error[WVF0013]: undefined reference 'Trust.formaton' (no such concept.facet in document)\n\
+             \x20--> power.wvf:3:16\n\
+             \x20 |\n\
+             3 |     - corrupts &Trust.formaton\n\
+             \x20 |                ^^^^^^^^^^^^^^^\n"
+        );
+    }
+
+    #[test]
+    fn test_render_secondary_label() {
+        let source = "Trust\n  .formation\n\n  .erosion\n    - fast !\n";
+        let result = validate(source);
+        let rendered = Renderer::new().render_diagnostic(&result.errors[0], source, "trust.wvf");
+
+        assert_eq!(
+            rendered,
+            "error[WVF0005]: facet 'formation' has no claims\n\
+             \x20--> trust.wvf:2:3\n\
+             \x20 |\n\
+             2 |   .formation\n\
+             \x20 |   ^^^^^^^^^^\n\
+             ...\n\
+             4 |   .erosion\n\
+             \x20 |   -------- expected a claim before this line\n"
+        );
+    }
+
+    #[test]
+    fn test_render_fix_help() {
+        let source = "Power\n  core\n    - corrupts\n";
+        let result = validate(source);
+        let rendered = Renderer::new().render_diagnostic(&result.errors[0], source, "power.wvf");
+
+        assert!(rendered.ends_with("  |   ^^^^\n  = help: add the '.' facet prefix\n"), "{}", rendered);
+    }
+
+    #[test]
+    fn test_render_summary() {
+        let renderer = Renderer::new();
+        let valid = validate("Power\n  .core\n    - corrupts");
+        assert_eq!(renderer.render_summary(&valid, "a.wvf"), "a.wvf: valid\n");
+
+        let warned = validate("Power\n  .core\n    - ^ corrupts");
+        assert_eq!(renderer.render_summary(&warned, "a.wvf"), "a.wvf: valid, 1 warning\n");
+
+        let invalid = validate("Power\n  .core\n    - ^ corrupts &A.b &C.d");
+        assert_eq!(renderer.render_summary(&invalid, "a.wvf"), "a.wvf: 2 errors, 1 warning\n");
+    }
+
+    #[test]
+    fn test_render_in_source_order_with_color() {
+        let source = "Power\n  .core\n    - ^ corrupts &A.b";
+        let result = validate(source);
+
+        let plain = Renderer::new().render(&result, source, "a.wvf");
+        let warning = plain.find("warning[WVF0022]").unwrap();
+        let error = plain.find("error[WVF0013]").unwrap();
+        assert!(warning < error, "Expected source order:\n{}", plain);
+        assert!(!plain.contains('\x1b'));
+
+        let colored = Renderer::new().color(true).render(&result, source, "a.wvf");
+        assert!(colored.contains("\x1b[1;31merror[WVF0013]\x1b[0m"));
+    }
+}
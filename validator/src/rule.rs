@@ -0,0 +1,541 @@
+//! Rules: the checks run over a parsed document
+//!
+//! A [`Rule`] visits the Concept → Facet → Claim tree and reports diagnostics
+//! through a [`RuleContext`]. Every built-in check is a rule, and a
+//! [`Validator`](crate::Validator) runs extra rules alongside them:
+//!
+//! ```
+//! use worldview_validator::rule::{Rule, RuleContext};
+//! use worldview_validator::{Claim, Concept, Facet, Validator};
+//!
+//! /// Every claim under `.security` must cite a source
+//! struct SecuritySources;
+//!
+//! impl Rule for SecuritySources {
+//!     fn name(&self) -> &str {
+//!         "security-sources"
+//!     }
+//!
+//!     fn check_claim(&self, _: &Concept, facet: &Facet, claim: &Claim, cx: &mut RuleContext) {
+//!         if facet.name == "security" && claim.data.sources.is_empty() {
+//!             cx.emit(claim.span, "ORG0001", "security claims must cite a @source");
+//!         }
+//!     }
+//! }
+//!
+//! let validator = Validator::new().rule(SecuritySources);
+//! let result = validator.validate("Vault\n  .security\n    - sealed\n");
+//! assert_eq!(result.warnings[0].code(), "ORG0001");
+//! ```
+
+use crate::format::format_claim;
+use crate::lexer::{self, TokenKind};
+use crate::lint::{Level, LintConfig};
+use crate::{Claim, Concept, Document, Facet, Reference, Span, ValidationError, is_identifier, suggest_references};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+
+/// A check over a parsed document
+///
+/// Each callback is called once per element, in document order; all of them do
+/// nothing by default. A rule with a default level is a lint: a
+/// [`LintConfig`] can allow, warn about or deny it by name.
+pub trait Rule {
+    /// The rule's name, e.g. `long-claim`
+    fn name(&self) -> &str;
+
+    /// Level when nothing configures it, or `None` if the rule is not a lint
+    /// and its diagnostics are reported at their own severity
+    fn default_level(&self) -> Option<Level> {
+        Some(Level::Warn)
+    }
+
+    /// Called once, before any concept
+    fn check_document(&self, _document: &Document, _cx: &mut RuleContext) {}
+
+    fn check_concept(&self, _concept: &Concept, _cx: &mut RuleContext) {}
+
+    fn check_facet(&self, _concept: &Concept, _facet: &Facet, _cx: &mut RuleContext) {}
+
+    fn check_claim(&self, _concept: &Concept, _facet: &Facet, _claim: &Claim, _cx: &mut RuleContext) {}
+}
+
+/// Where rules report diagnostics
+pub struct RuleContext<'a> {
+    lints: &'a LintConfig,
+    valid_refs: &'a HashSet<Reference>,
+    /// Level of the rule being run, `None` if it is not a lint
+    pub(crate) level: Option<Level>,
+    pub(crate) errors: Vec<ValidationError>,
+    pub(crate) warnings: Vec<ValidationError>,
+}
+
+impl<'a> RuleContext<'a> {
+    pub(crate) fn new(lints: &'a LintConfig, valid_refs: &'a HashSet<Reference>) -> Self {
+        RuleContext {
+            lints,
+            valid_refs,
+            level: None,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// The lint configuration, for rule options such as `max_claim_words`
+    pub fn lints(&self) -> &LintConfig {
+        self.lints
+    }
+
+    /// Whether a reference names a concept or facet defined in the document (or workspace)
+    pub fn is_defined(&self, reference: &Reference) -> bool {
+        self.valid_refs.contains(reference)
+    }
+
+    /// Report a diagnostic at the running rule's level
+    pub fn report(&mut self, diagnostic: ValidationError) {
+        let level = match self.level {
+            Some(level) => level,
+            None if diagnostic.is_warning() => Level::Warn,
+            None => Level::Deny,
+        };
+        match level {
+            Level::Allow => {}
+            Level::Warn if !self.lints.deny_warnings => self.warnings.push(diagnostic),
+            Level::Warn | Level::Deny => self.errors.push(diagnostic),
+        }
+    }
+
+    /// Report an error whatever the running rule's level
+    #[cfg(feature = "rhai")]
+    pub(crate) fn fail(&mut self, diagnostic: ValidationError) {
+        self.errors.push(diagnostic);
+    }
+
+    /// Report a diagnostic of a rule defined outside this crate
+    ///
+    /// Codes starting with `WVF` belong to the built-in diagnostics; use a
+    /// prefix of your own, such as `ORG0001`.
+    pub fn emit(&mut self, span: Span, code: impl Into<String>, message: impl Into<String>) {
+        self.report(ValidationError::Custom {
+            span,
+            code: code.into(),
+            message: message.into(),
+        });
+    }
+}
+
+/// The built-in rules, in the order they run
+pub fn builtin() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(Duplicates),
+        Box::new(Hierarchy),
+        Box::new(Names),
+        Box::new(ClaimSyntax),
+        Box::new(StandaloneModifier),
+        Box::new(UnreferenceableConcept),
+        Box::new(LongClaim),
+        Box::new(MissingSource),
+    ]
+}
+
+/// Run `rules` over `document`, skipping allowed lints
+pub(crate) fn run(rules: &[Box<dyn Rule>], document: &Document, cx: &mut RuleContext) {
+    let rules: Vec<(&dyn Rule, Option<Level>)> = rules
+        .iter()
+        .map(|rule| {
+            let level = rule.default_level().map(|default| cx.lints.level(rule.name(), default));
+            (rule.as_ref(), level)
+        })
+        .filter(|(_, level)| *level != Some(Level::Allow))
+        .collect();
+
+    let each = |cx: &mut RuleContext, check: &dyn Fn(&dyn Rule, &mut RuleContext)| {
+        for (rule, level) in &rules {
+            cx.level = *level;
+            check(*rule, cx);
+        }
+        cx.level = None;
+    };
+
+    each(cx, &|rule, cx| rule.check_document(document, cx));
+    for concept in &document.concepts {
+        each(cx, &|rule, cx| rule.check_concept(concept, cx));
+        for facet in &concept.facets {
+            each(cx, &|rule, cx| rule.check_facet(concept, facet, cx));
+            for claim in &facet.claims {
+                each(cx, &|rule, cx| rule.check_claim(concept, facet, claim, cx));
+            }
+        }
+    }
+}
+
+/// Concepts, facets and claims declared more than once
+struct Duplicates;
+
+impl Rule for Duplicates {
+    fn name(&self) -> &str {
+        "duplicates"
+    }
+
+    fn default_level(&self) -> Option<Level> {
+        None
+    }
+
+    fn check_document(&self, document: &Document, cx: &mut RuleContext) {
+        let mut concepts_seen: HashMap<&str, Span> = HashMap::new();
+        for concept in &document.concepts {
+            if let Some(&first) = concepts_seen.get(concept.name.as_str()) {
+                cx.report(ValidationError::DuplicateConcept {
+                    span: concept.span,
+                    concept: concept.name.clone(),
+                    first,
+                });
+            } else {
+                concepts_seen.insert(&concept.name, concept.span);
+            }
+        }
+    }
+
+    fn check_concept(&self, concept: &Concept, cx: &mut RuleContext) {
+        let mut facets_seen: HashMap<&str, Span> = HashMap::new();
+        for facet in &concept.facets {
+            if let Some(&first) = facets_seen.get(facet.name.as_str()) {
+                cx.report(ValidationError::DuplicateFacet {
+                    span: facet.span,
+                    facet: facet.name.clone(),
+                    first,
+                });
+            } else {
+                facets_seen.insert(&facet.name, facet.span);
+            }
+        }
+    }
+
+    fn check_facet(&self, _concept: &Concept, facet: &Facet, cx: &mut RuleContext) {
+        // Claims are compared in canonical form, so spacing differences don't hide repeats
+        let mut claims_seen: HashMap<String, Span> = HashMap::new();
+        for claim in &facet.claims {
+            match claims_seen.entry(format_claim(&claim.data)) {
+                Entry::Occupied(first) => cx.report(ValidationError::DuplicateClaim {
+                    span: claim.span,
+                    first: *first.get(),
+                }),
+                Entry::Vacant(slot) => {
+                    slot.insert(claim.span);
+                }
+            }
+        }
+    }
+}
+
+/// Concepts without facets, and facets without claims
+struct Hierarchy;
+
+impl Rule for Hierarchy {
+    fn name(&self) -> &str {
+        "hierarchy"
+    }
+
+    fn default_level(&self) -> Option<Level> {
+        None
+    }
+
+    fn check_document(&self, document: &Document, cx: &mut RuleContext) {
+        for (ci, concept) in document.concepts.iter().enumerate() {
+            let next_concept = document.concepts.get(ci + 1).map(|c| c.span);
+            if concept.facets.is_empty() {
+                cx.report(ValidationError::ConceptWithoutFacets {
+                    span: concept.span,
+                    concept: concept.name.clone(),
+                    next: next_concept,
+                });
+            }
+            for (fi, facet) in concept.facets.iter().enumerate() {
+                if facet.claims.is_empty() {
+                    cx.report(ValidationError::FacetWithoutClaims {
+                        span: facet.span,
+                        facet: facet.name.clone(),
+                        next: concept.facets.get(fi + 1).map(|f| f.span).or(next_concept),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Facet names that are not identifiers
+struct Names;
+
+impl Rule for Names {
+    fn name(&self) -> &str {
+        "names"
+    }
+
+    fn default_level(&self) -> Option<Level> {
+        None
+    }
+
+    fn check_facet(&self, _concept: &Concept, facet: &Facet, cx: &mut RuleContext) {
+        // An empty name is already reported while tokenizing
+        if !facet.name.is_empty() && !is_identifier(&facet.name) {
+            cx.report(ValidationError::InvalidIdentifier {
+                span: facet.span,
+                kind: "facet",
+                name: facet.name.clone(),
+            });
+        }
+    }
+}
+
+/// Claim syntax: inline elements, references, brief forms and evolution markers
+struct ClaimSyntax;
+
+impl Rule for ClaimSyntax {
+    fn name(&self) -> &str {
+        "claim-syntax"
+    }
+
+    fn default_level(&self) -> Option<Level> {
+        None
+    }
+
+    fn check_claim(&self, _concept: &Concept, _facet: &Facet, claim: &Claim, cx: &mut RuleContext) {
+        let claim = &claim.data;
+
+        // Check for empty claim text
+        if claim.text.is_empty() {
+            cx.report(ValidationError::EmptyClaimText { span: claim.text_span });
+        }
+
+        // Check for empty conditions
+        for cond in &claim.conditions {
+            if cond.is_empty() {
+                cx.report(ValidationError::EmptyCondition { span: cond.span });
+            }
+        }
+
+        // Check for empty and malformed sources
+        for src in &claim.sources {
+            if src.is_empty() {
+                cx.report(ValidationError::EmptySource { span: src.span });
+            } else if !is_identifier(src) {
+                cx.report(ValidationError::InvalidIdentifier {
+                    span: src.span,
+                    kind: "source",
+                    name: src.value.clone(),
+                });
+            }
+        }
+
+        // Check for empty references
+        for reference in &claim.references {
+            if reference.is_empty() {
+                cx.report(ValidationError::EmptyReference { span: reference.span });
+            }
+        }
+
+        // Validate reference format (should be Concept or Concept.facet)
+        for reference in &claim.references {
+            if !reference.is_empty() && !reference.is_well_formed() {
+                cx.report(ValidationError::InvalidReferenceFormat {
+                    span: reference.span,
+                    reference: reference.value.to_string(),
+                });
+            }
+        }
+
+        // Validate references point to existing concepts or concept.facet pairs
+        for reference in &claim.references {
+            if reference.is_well_formed() && !cx.is_defined(&reference.value) {
+                let suggestions = suggest_references(&reference.value, cx.valid_refs);
+                cx.report(ValidationError::UndefinedReference {
+                    span: reference.span,
+                    reference: reference.value.to_string(),
+                    suggestions,
+                });
+            }
+        }
+
+        // Validate brief forms have operands
+        for bf in &claim.brief_forms {
+            if bf.left_operand.is_empty() {
+                cx.report(ValidationError::BriefFormMissingLeftOperand {
+                    span: bf.span,
+                    operator: bf.operator.clone(),
+                });
+            }
+            if bf.right_operand.is_empty() {
+                cx.report(ValidationError::BriefFormMissingRightOperand {
+                    span: bf.span,
+                    operator: bf.operator.clone(),
+                });
+            }
+        }
+
+        // An evolution marker left in the claim text was never closed
+        if let Some(open) = lexer::lex_claim(&claim.text, claim.text_span)
+            .into_iter()
+            .find(|t| t.kind == TokenKind::EvolutionOpen)
+        {
+            let span = claim.text_span.subspan(&claim.text, open.span.start - claim.text_span.start..claim.text.len());
+            cx.report(ValidationError::UnclosedEvolutionMarker { span });
+        }
+
+        // Validate evolution marker content if present
+        if let Some(ref evo) = claim.evolution
+            && evo.prior_belief.is_empty()
+        {
+            cx.report(ValidationError::EmptyEvolutionMarker { span: evo.span });
+        }
+    }
+}
+
+/// Modifiers with no term to inflect: at the start, or after an operator
+///
+/// Space-separated modifiers that follow a term are valid (e.g., "fast !").
+struct StandaloneModifier;
+
+impl Rule for StandaloneModifier {
+    fn name(&self) -> &str {
+        "standalone-modifier"
+    }
+
+    fn check_claim(&self, _concept: &Concept, _facet: &Facet, claim: &Claim, cx: &mut RuleContext) {
+        for modifier in &claim.data.modifiers {
+            if modifier.attached_to.is_empty() {
+                cx.report(ValidationError::StandaloneModifier {
+                    span: modifier.span,
+                    modifier: modifier.symbol.to_string(),
+                });
+            }
+        }
+    }
+}
+
+/// Concepts whose names cannot be referenced
+struct UnreferenceableConcept;
+
+impl Rule for UnreferenceableConcept {
+    fn name(&self) -> &str {
+        "unreferenceable-concept"
+    }
+
+    fn check_concept(&self, concept: &Concept, cx: &mut RuleContext) {
+        if !is_identifier(&concept.name) {
+            cx.report(ValidationError::UnreferenceableConcept {
+                span: concept.span,
+                concept: concept.name.clone(),
+            });
+        }
+    }
+}
+
+/// Claim text longer than `max_claim_words`
+struct LongClaim;
+
+impl Rule for LongClaim {
+    fn name(&self) -> &str {
+        "long-claim"
+    }
+
+    fn default_level(&self) -> Option<Level> {
+        Some(Level::Allow)
+    }
+
+    fn check_claim(&self, _concept: &Concept, _facet: &Facet, claim: &Claim, cx: &mut RuleContext) {
+        let max = cx.lints().max_claim_words;
+        // Standalone modifiers and operators are not words
+        let words = claim.data.text.split_whitespace().filter(|w| w.chars().any(char::is_alphanumeric)).count();
+        if words > max {
+            cx.report(ValidationError::LongClaim {
+                span: claim.data.text_span,
+                words,
+                max,
+            });
+        }
+    }
+}
+
+/// Claims that cite no source
+struct MissingSource;
+
+impl Rule for MissingSource {
+    fn name(&self) -> &str {
+        "missing-source"
+    }
+
+    fn default_level(&self) -> Option<Level> {
+        Some(Level::Allow)
+    }
+
+    fn check_claim(&self, _concept: &Concept, _facet: &Facet, claim: &Claim, cx: &mut RuleContext) {
+        if claim.data.sources.is_empty() {
+            cx.report(ValidationError::MissingSource { span: claim.span });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Validator;
+    use crate::lint::LINTS;
+
+    /// Every facet must have at least two claims
+    struct TwoClaims;
+
+    impl Rule for TwoClaims {
+        fn name(&self) -> &str {
+            "two-claims"
+        }
+
+        fn check_facet(&self, _concept: &Concept, facet: &Facet, cx: &mut RuleContext) {
+            if facet.claims.len() < 2 {
+                cx.emit(facet.span, "TEAM0001", format!("facet '{}' has a single claim", facet.name));
+            }
+        }
+    }
+
+    const SOURCE: &str = "Trust\n  .formation\n    - slow\n    - fast\n  .erosion\n    - fast\n";
+
+    #[test]
+    fn test_builtin_lints_are_registered() {
+        let rules = builtin();
+        for lint in LINTS {
+            let rule = rules.iter().find(|r| r.name() == lint.name).expect("Expected a rule for each lint");
+            assert_eq!(rule.default_level(), Some(lint.default), "{}", lint.name);
+        }
+        for rule in rules.iter().filter(|r| r.default_level().is_some()) {
+            assert!(LINTS.iter().any(|l| l.name == rule.name()), "{} is not in LINTS", rule.name());
+        }
+    }
+
+    #[test]
+    fn test_custom_rule_runs_alongside_builtins() {
+        let result = Validator::new().rule(TwoClaims).validate(&format!("{}Power\n", SOURCE));
+        assert!(matches!(&result.errors[..], [ValidationError::ConceptWithoutFacets { .. }]));
+        match &result.warnings[..] {
+            [warning @ ValidationError::Custom { span, .. }] => {
+                assert_eq!(warning.code(), "TEAM0001");
+                assert_eq!(warning.message(), "facet 'erosion' has a single claim");
+                assert_eq!(span.line, 5);
+            }
+            other => panic!("Expected a custom warning, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_custom_rule_levels() {
+        let mut lints = LintConfig::new();
+        lints.set_level("two-claims", Level::Deny).unwrap();
+        let validator = Validator::new().rule(TwoClaims).lints(lints.clone());
+        assert!(validator.check_lints().is_ok());
+        assert!(matches!(&validator.validate(SOURCE).errors[..], [ValidationError::Custom { .. }]));
+
+        lints.set_level("two-claims", Level::Allow).unwrap();
+        assert!(Validator::new().rule(TwoClaims).lints(lints.clone()).validate(SOURCE).is_valid());
+
+        // Without the rule, the configuration names a lint that doesn't exist
+        assert!(Validator::new().lints(lints).check_lints().is_err());
+    }
+}
@@ -0,0 +1,273 @@
+//! Rules written as [Rhai](https://rhai.rs) scripts (`rhai` feature)
+//!
+//! A script defines any of three functions, called for each element of the
+//! document in order, and reports diagnostics with `emit(span, code, message)`:
+//!
+//! ```rhai
+//! fn concept(concept) { }            // #{ name, span, facets: [names] }
+//! fn facet(concept, facet) { }       // #{ name, span, claims: [texts] }
+//! fn claim(concept, facet, claim) {  // #{ text, conditions, sources, references, span }
+//!     if facet.name == "security" && claim.sources.is_empty() {
+//!         emit(claim.span, "ORG0001", "security claims must cite a @source");
+//!     }
+//! }
+//! ```
+//!
+//! A `span` has `line` and `column` properties. Like any lint, a script rule is
+//! configured by its name and warns by default. A script that raises an error,
+//! or exceeds a limit on operations, value sizes or call depth, fails validation
+//! with a `ScriptRuleFailed` error (WVF0036).
+
+use crate::lint::Level;
+use crate::rule::{Rule, RuleContext};
+use crate::{Claim, Concept, Facet, Span, ValidationError};
+use rhai::{AST, Array, CallFnOptions, Dynamic, Engine, FuncArgs, Map, Scope};
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+use thiserror::Error;
+
+/// Most operations a script may run per call, so a runaway loop fails instead of hanging
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Most bytes in a script's string, so a runaway script fails instead of exhausting memory
+const MAX_STRING_SIZE: usize = 1 << 20;
+
+/// Most elements in a script's array
+const MAX_ARRAY_SIZE: usize = 100_000;
+
+/// Most properties in a script's object map
+const MAX_MAP_SIZE: usize = 10_000;
+
+/// Deepest nesting of function calls, so runaway recursion fails instead of overflowing the stack
+const MAX_CALL_LEVELS: usize = 64;
+
+/// Errors loading a script rule
+#[derive(Error, Debug)]
+pub enum ScriptError {
+    #[error("cannot read {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("rule '{name}': {message}")]
+    Compile { name: String, message: String },
+}
+
+/// Diagnostics emitted by the running script: span, code and message
+type Emitted = Rc<RefCell<Vec<(Span, String, String)>>>;
+
+/// A [`Rule`] running a Rhai script
+pub struct ScriptRule {
+    name: String,
+    engine: Engine,
+    ast: AST,
+    emitted: Emitted,
+}
+
+impl ScriptRule {
+    /// Compile a script rule from source
+    pub fn new(name: &str, source: &str) -> Result<Self, ScriptError> {
+        let emitted = Emitted::default();
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(MAX_OPERATIONS)
+            .set_max_string_size(MAX_STRING_SIZE)
+            .set_max_array_size(MAX_ARRAY_SIZE)
+            .set_max_map_size(MAX_MAP_SIZE)
+            .set_max_call_levels(MAX_CALL_LEVELS);
+        // Keep stdout for the validation report
+        engine.on_print(|text| eprintln!("{}", text));
+        engine
+            .register_type_with_name::<Span>("Span")
+            .register_get("line", |span: &mut Span| span.line as i64)
+            .register_get("column", |span: &mut Span| span.column as i64);
+        let sink = emitted.clone();
+        engine.register_fn("emit", move |span: Span, code: &str, message: &str| {
+            sink.borrow_mut().push((span, code.to_string(), message.to_string()));
+        });
+
+        let ast = engine.compile(source).map_err(|e| ScriptError::Compile {
+            name: name.to_string(),
+            message: e.to_string(),
+        })?;
+        Ok(ScriptRule {
+            name: name.to_string(),
+            engine,
+            ast,
+            emitted,
+        })
+    }
+
+    /// Read and compile a script rule
+    pub fn load(name: &str, path: &Path) -> Result<Self, ScriptError> {
+        let source = std::fs::read_to_string(path).map_err(|source| ScriptError::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+        Self::new(name, &source)
+    }
+
+    /// Call a script function if the script defines it, reporting what it emits
+    fn call(&self, function: &str, arity: usize, span: Span, args: impl FuncArgs, cx: &mut RuleContext) {
+        if !self.ast.iter_functions().any(|f| f.name == function && f.params.len() == arity) {
+            return;
+        }
+        let options = CallFnOptions::new().eval_ast(false);
+        let result = self.engine.call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &self.ast, function, args);
+        for (span, code, message) in self.emitted.borrow_mut().drain(..) {
+            cx.emit(span, code, message);
+        }
+        // A failing script fails validation, whatever the rule's level
+        if let Err(e) = result {
+            cx.fail(ValidationError::ScriptRuleFailed {
+                span,
+                rule: self.name.clone(),
+                message: e.to_string(),
+            });
+        }
+    }
+}
+
+impl Rule for ScriptRule {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn default_level(&self) -> Option<Level> {
+        Some(Level::Warn)
+    }
+
+    fn check_concept(&self, concept: &Concept, cx: &mut RuleContext) {
+        self.call("concept", 1, concept.span, (concept_map(concept),), cx);
+    }
+
+    fn check_facet(&self, concept: &Concept, facet: &Facet, cx: &mut RuleContext) {
+        self.call("facet", 2, facet.span, (concept_map(concept), facet_map(facet)), cx);
+    }
+
+    fn check_claim(&self, concept: &Concept, facet: &Facet, claim: &Claim, cx: &mut RuleContext) {
+        let args = (concept_map(concept), facet_map(facet), claim_map(claim));
+        self.call("claim", 3, claim.span, args, cx);
+    }
+}
+
+fn concept_map(concept: &Concept) -> Map {
+    let mut map = Map::new();
+    map.insert("name".into(), concept.name.clone().into());
+    map.insert("span".into(), Dynamic::from(concept.span));
+    map.insert("facets".into(), strings(concept.facets.iter().map(|f| f.name.as_str())).into());
+    map
+}
+
+fn facet_map(facet: &Facet) -> Map {
+    let mut map = Map::new();
+    map.insert("name".into(), facet.name.clone().into());
+    map.insert("span".into(), Dynamic::from(facet.span));
+    map.insert("claims".into(), strings(facet.claims.iter().map(|c| c.data.text.as_str())).into());
+    map
+}
+
+fn claim_map(claim: &Claim) -> Map {
+    let claim_data = &claim.data;
+    let references: Vec<String> = claim_data.references.iter().map(|r| r.value.to_string()).collect();
+    let mut map = Map::new();
+    map.insert("text".into(), claim_data.text.clone().into());
+    map.insert("span".into(), Dynamic::from(claim.span));
+    map.insert("conditions".into(), strings(claim_data.conditions.iter().map(|c| c.value.as_str())).into());
+    map.insert("sources".into(), strings(claim_data.sources.iter().map(|s| s.value.as_str())).into());
+    map.insert("references".into(), strings(references.iter().map(String::as_str)).into());
+    map
+}
+
+fn strings<'a>(values: impl Iterator<Item = &'a str>) -> Array {
+    values.map(|value| value.to_string().into()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Validator;
+    use crate::lint::LintConfig;
+
+    const SOURCE: &str = "Vault\n  .security\n    - sealed @audit\n    - guarded\n  .history\n    - old\n";
+
+    const SCRIPT: &str = r#"
+        fn facet(concept, facet) {
+            if facet.claims.len() < 2 {
+                emit(facet.span, "ORG0002", `${concept.name}.${facet.name} has a single claim`);
+            }
+        }
+
+        fn claim(concept, facet, claim) {
+            if facet.name == "security" && claim.sources.is_empty() {
+                emit(claim.span, "ORG0001", "security claims must cite a @source");
+            }
+        }
+    "#;
+
+    fn validator(script: &str) -> Validator {
+        Validator::new().rule(ScriptRule::new("house-style", script).unwrap())
+    }
+
+    #[test]
+    fn test_script_emits_diagnostics() {
+        let result = validator(SCRIPT).validate(SOURCE);
+        assert!(result.is_valid());
+        let found: Vec<(usize, &str, String)> =
+            result.warnings.iter().map(|w| (w.line(), w.code(), w.message())).collect();
+        assert_eq!(
+            found,
+            [
+                (4, "ORG0001", "security claims must cite a @source".to_string()),
+                (5, "ORG0002", "Vault.history has a single claim".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_script_rule_levels() {
+        let mut lints = LintConfig::new();
+        lints.set_level("house-style", Level::Deny).unwrap();
+        let result = validator(SCRIPT).lints(lints.clone()).validate(SOURCE);
+        assert_eq!(result.errors.len(), 2);
+
+        lints.set_level("house-style", Level::Allow).unwrap();
+        assert!(validator(SCRIPT).lints(lints).validate(SOURCE).warnings.is_empty());
+    }
+
+    #[test]
+    fn test_script_errors() {
+        assert!(matches!(ScriptRule::new("broken", "fn claim(c {"), Err(ScriptError::Compile { .. })));
+
+        let result = validator("fn concept(concept) { concept.name.no_such_method() }").validate(SOURCE);
+        match &result.errors[..] {
+            [error @ ValidationError::ScriptRuleFailed { rule, .. }] => {
+                assert_eq!(rule, "house-style");
+                assert_eq!(error.code(), "WVF0036");
+                assert!(error.message().starts_with("rule 'house-style' failed"), "{}", error);
+            }
+            other => panic!("Expected a script failure, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_script_limits() {
+        let runaway = [
+            ("fn concept(concept) { loop {} }", "Too many operations"),
+            ("fn concept(concept) { let s = \"x\"; loop { s += s; } }", "Length of string too large"),
+            ("fn concept(concept) { let a = [0]; loop { a += a; } }", "Size of array/BLOB too large"),
+            ("fn concept(concept) { let m = #{}; for i in 0..100000 { m[`${i}`] = i; } m.keys() }", "Size of object map too large"),
+            ("fn deeper(n) { deeper(n + 1) } fn concept(concept) { deeper(0) }", "Stack overflow"),
+        ];
+        for (script, limit) in runaway {
+            let result = validator(script).validate(SOURCE);
+            match &result.errors[..] {
+                [ValidationError::ScriptRuleFailed { message, .. }, ..] => assert!(message.contains(limit), "{}", message),
+                other => panic!("Expected a script failure, got {:?}", other),
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Grammar-based validation against spec/grammar.pest
+//!
+//! The format has two definitions: the hand-written rules in this crate and
+//! the PEG grammar in `spec/grammar.pest`. [`validate_strict`] checks a
+//! document against both. The fixture corpus in `spec/fixtures` is run
+//! through each, so the two cannot drift apart unnoticed.
+
+use crate::{Span, ValidationError, ValidationResult, validate};
+use pest::Parser;
+use pest::error::{Error, InputLocation};
+use pest_derive::Parser;
+
+#[derive(Parser)]
+#[grammar = "../../spec/grammar.pest"]
+struct WorldviewParser;
+
+/// Validate a document with the hand-written rules and the spec grammar
+///
+/// If the grammar rejects the document, a [`ValidationError::GrammarMismatch`]
+/// is added to the result, even when the hand-written rules already report errors.
+pub fn validate_strict(input: &str) -> ValidationResult {
+    let mut result = validate(input);
+    if let Err(error) = parse_grammar(input) {
+        result.errors.push(error);
+    }
+    result
+}
+
+/// Check a document against the spec grammar alone
+pub fn parse_grammar(input: &str) -> Result<(), ValidationError> {
+    WorldviewParser::parse(Rule::document, input)
+        .map(|_| ())
+        .map_err(|error| mismatch(input, &error))
+}
+
+/// Convert a pest error into a diagnostic at the failing position
+fn mismatch(input: &str, error: &Error<Rule>) -> ValidationError {
+    let start = match error.location {
+        InputLocation::Pos(pos) => pos,
+        InputLocation::Span((start, _)) => start,
+    };
+    let line_start = input[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = input[start..].find('\n').map_or(input.len(), |i| start + i);
+    let span = Span {
+        line: input[..start].matches('\n').count() + 1,
+        column: input[line_start..start].chars().count() + 1,
+        start,
+        end: line_end.max(start),
+    };
+    ValidationError::GrammarMismatch {
+        span,
+        expected: error.variant.message().into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::fixtures;
+
+    #[test]
+    fn test_grammar_agrees_with_fixture_corpus() {
+        for (name, source) in fixtures("valid") {
+            assert_eq!(parse_grammar(&source), Ok(()), "{name} should match the grammar");
+        }
+        // Undefined references and duplicates are beyond what a grammar can check
+        for (name, source) in fixtures("semantic") {
+            assert_eq!(parse_grammar(&source), Ok(()), "{name} should match the grammar");
+            assert!(!validate_strict(&source).is_valid(), "{name} should be invalid");
+        }
+        for (name, source) in fixtures("invalid") {
+            assert!(parse_grammar(&source).is_err(), "{name} should not match the grammar");
+        }
+    }
+
+    #[test]
+    fn test_example_matches_grammar() {
+        assert_eq!(parse_grammar(include_str!("../../example.wvf")), Ok(()));
+    }
+
+    #[test]
+    fn test_mismatch_location() {
+        let input = "Power\n  .core\n    - corrupts @history,\n";
+        let result = validate_strict(input);
+        let mismatch = result
+            .errors
+            .iter()
+            .find(|e| matches!(e, ValidationError::GrammarMismatch { .. }))
+            .expect("Expected a grammar mismatch");
+        assert_eq!(mismatch.line(), 3);
+    }
+}
@@ -0,0 +1,159 @@
+//! Workspaces: several documents validated together
+//!
+//! The documents of a workspace share one namespace. A `&Concept` or
+//! `&Concept.facet` reference in any file resolves against the concepts
+//! declared in every file, and each concept may be declared in only one file
+//! (see `CrossFileDuplicateConcept`).
+
+use crate::{Draft, Span, ValidationError, ValidationResult, Validator, collect_valid_references};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// One document of a workspace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceFile {
+    pub path: PathBuf,
+    pub source: String,
+}
+
+impl WorkspaceFile {
+    /// Read a document from disk
+    pub fn read(path: &Path) -> Result<Self, std::io::Error> {
+        Ok(WorkspaceFile {
+            path: path.to_path_buf(),
+            source: std::fs::read_to_string(path)?,
+        })
+    }
+}
+
+/// Validate documents together with the built-in rules, resolving references across all of them
+///
+/// Returns one result per file, in the order given. When a concept is declared
+/// in several files, the first file keeps it and the others get an error.
+pub fn validate_workspace(files: &[WorkspaceFile]) -> Vec<ValidationResult> {
+    Validator::new().validate_workspace(files)
+}
+
+impl Validator {
+    /// Validate documents together, like [`validate_workspace`]
+    pub fn validate_workspace(&self, files: &[WorkspaceFile]) -> Vec<ValidationResult> {
+        let mut drafts: Vec<Draft> = files.iter().map(|file| Draft::new(&file.source)).collect();
+
+        let mut valid_refs = HashSet::new();
+        for draft in &drafts {
+            valid_refs.extend(collect_valid_references(&draft.document));
+        }
+
+        // Duplicates within one file are reported by `validate` itself
+        let mut declared: HashMap<String, (usize, Span)> = HashMap::new();
+        for (idx, draft) in drafts.iter_mut().enumerate() {
+            for concept in &draft.document.concepts {
+                match declared.entry(concept.name.clone()) {
+                    Entry::Occupied(first) if first.get().0 != idx => {
+                        let (first_idx, first) = *first.get();
+                        draft.diagnostics.push(ValidationError::CrossFileDuplicateConcept {
+                            span: concept.span,
+                            concept: concept.name.clone(),
+                            first_file: files[first_idx].path.display().to_string(),
+                            first,
+                        });
+                    }
+                    Entry::Occupied(_) => {}
+                    Entry::Vacant(slot) => {
+                        slot.insert((idx, concept.span));
+                    }
+                }
+            }
+        }
+
+        drafts.into_iter().map(|draft| self.finish(draft, &valid_refs)).collect()
+    }
+}
+
+/// Every `.wvf` file under a directory, recursively, in sorted order
+pub fn find_documents(dir: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+    let mut documents = Vec::new();
+    collect_documents(dir, &mut documents)?;
+    documents.sort();
+    Ok(documents)
+}
+
+fn collect_documents(dir: &Path, documents: &mut Vec<PathBuf>) -> Result<(), std::io::Error> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_documents(&path, documents)?;
+        } else if path.extension().is_some_and(|ext| ext == "wvf") {
+            documents.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate;
+
+    fn file(path: &str, source: &str) -> WorkspaceFile {
+        WorkspaceFile {
+            path: PathBuf::from(path),
+            source: source.to_string(),
+        }
+    }
+
+    fn workspace() -> Vec<WorkspaceFile> {
+        vec![
+            file("ethics.wvf", "Honesty\n  .practice\n    - builds trust &Trust.formation\n"),
+            file("people.wvf", "Trust\n  .formation\n    - slow &Honesty\n"),
+        ]
+    }
+
+    #[test]
+    fn test_references_resolve_across_files() {
+        let files = workspace();
+        assert!(!validate(&files[0].source).is_valid());
+
+        let results = validate_workspace(&files);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.is_valid()), "{:?}", results);
+    }
+
+    #[test]
+    fn test_undefined_references_suggest_targets_in_other_files() {
+        let mut files = workspace();
+        files[0].source = files[0].source.replace("Trust.formation", "Trust.fromation");
+
+        let results = validate_workspace(&files);
+        assert!(matches!(
+            &results[0].errors[..],
+            [ValidationError::UndefinedReference { suggestions, .. }] if suggestions == &["Trust.formation"]
+        ));
+        assert!(results[1].is_valid());
+    }
+
+    #[test]
+    fn test_concepts_declared_in_two_files() {
+        let mut files = workspace();
+        files.push(file("more.wvf", "Trust\n  .erosion\n    - fast\n"));
+
+        let results = validate_workspace(&files);
+        assert!(results[1].is_valid());
+        match &results[2].errors[..] {
+            [ValidationError::CrossFileDuplicateConcept { span, first_file, first, .. }] => {
+                assert_eq!(span.line, 1);
+                assert_eq!(first_file, "people.wvf");
+                assert_eq!(first.line, 1);
+            }
+            other => panic!("Expected CrossFileDuplicateConcept, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_duplicates_within_a_file_are_reported_once() {
+        let files = [file("a.wvf", "Trust\n  .formation\n    - slow\nTrust\n  .erosion\n    - fast\n")];
+        let results = validate_workspace(&files);
+        assert!(matches!(&results[0].errors[..], [ValidationError::DuplicateConcept { .. }]));
+    }
+}